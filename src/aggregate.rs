@@ -0,0 +1,323 @@
+use std::error::Error;
+use std::ffi::{c_char, CString};
+use std::ptr;
+use std::slice;
+
+use duckdb::core::{FlatVector, ListVector, LogicalTypeId, StructVector};
+use duckdb::ffi;
+use duckdb::vtab::arrow::WritableVector;
+
+use super::validate_constant_param;
+
+/// Logical type of an aggregate parameter or result.
+///
+/// `duckdb` does not wrap aggregate functions yet, so these are materialized
+/// through the C API directly when the function is registered.
+pub enum AggregateType {
+    Primitive(ffi::DUCKDB_TYPE),
+    List(Box<AggregateType>),
+    Struct(Vec<(&'static str, AggregateType)>),
+}
+
+impl From<LogicalTypeId> for AggregateType {
+    fn from(id: LogicalTypeId) -> Self {
+        Self::Primitive(id as ffi::DUCKDB_TYPE)
+    }
+}
+
+impl AggregateType {
+    pub fn list(child_type: Self) -> Self {
+        Self::List(Box::new(child_type))
+    }
+
+    pub fn struct_type(fields: Vec<(&'static str, Self)>) -> Self {
+        Self::Struct(fields)
+    }
+
+    unsafe fn create(&self) -> Result<ffi::duckdb_logical_type, Box<dyn Error>> {
+        match self {
+            Self::Primitive(id) => Ok(ffi::duckdb_create_logical_type(*id)),
+            Self::List(child_type) => {
+                let mut child = child_type.create()?;
+                let list = ffi::duckdb_create_list_type(child);
+                ffi::duckdb_destroy_logical_type(&mut child);
+                Ok(list)
+            }
+            Self::Struct(fields) => {
+                let names = fields
+                    .iter()
+                    .map(|(name, _)| CString::new(*name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut name_ptrs: Vec<*const c_char> = names.iter().map(|n| n.as_ptr()).collect();
+                let mut members = fields
+                    .iter()
+                    .map(|(_, member_type)| member_type.create())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let struct_type = ffi::duckdb_create_struct_type(
+                    members.as_mut_ptr(),
+                    name_ptrs.as_mut_ptr(),
+                    members.len() as ffi::idx_t,
+                );
+                for member in members.iter_mut() {
+                    ffi::duckdb_destroy_logical_type(member);
+                }
+                Ok(struct_type)
+            }
+        }
+    }
+}
+
+/// Duckdb aggregate function signature
+pub struct AggregateFunctionSignature {
+    parameters: Vec<AggregateType>,
+    return_type: AggregateType,
+}
+
+impl AggregateFunctionSignature {
+    pub fn exact(parameters: Vec<AggregateType>, return_type: AggregateType) -> Self {
+        Self {
+            parameters,
+            return_type,
+        }
+    }
+}
+
+/// The rows passed to [`VAggregate::update`], mirroring the accessors of `DataChunkHandle`.
+pub struct AggregateInput {
+    ptr: ffi::duckdb_data_chunk,
+}
+
+impl AggregateInput {
+    pub fn len(&self) -> usize {
+        unsafe { ffi::duckdb_data_chunk_get_size(self.ptr) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn num_columns(&self) -> usize {
+        unsafe { ffi::duckdb_data_chunk_get_column_count(self.ptr) as usize }
+    }
+
+    pub fn flat_vector(&self, idx: usize) -> FlatVector {
+        FlatVector::from(unsafe { ffi::duckdb_data_chunk_get_vector(self.ptr, idx as u64) })
+    }
+
+    pub fn list_vector(&self, idx: usize) -> ListVector {
+        ListVector::from(unsafe { ffi::duckdb_data_chunk_get_vector(self.ptr, idx as u64) })
+    }
+
+    pub fn struct_vector(&self, idx: usize) -> StructVector {
+        StructVector::from(unsafe { ffi::duckdb_data_chunk_get_vector(self.ptr, idx as u64) })
+    }
+}
+
+/// Reads a constant, non-negative integer parameter such as `ngram_width`.
+///
+/// Integer literals are typed before aggregates are bound, so these parameters
+/// are `BIGINT` rather than `UBIGINT` as in scalar functions.
+pub fn validate_count_param(
+    input: &AggregateInput,
+    col_idx: usize,
+    param_name: &str,
+) -> Result<usize, Box<dyn Error>> {
+    let value = validate_constant_param(
+        input
+            .flat_vector(col_idx)
+            .as_slice_with_len::<i64>(input.len()),
+        param_name,
+    )?;
+    usize::try_from(value).map_err(|_| format!("{} must not be negative", param_name).into())
+}
+
+/// Reads a constant `seed` parameter, which overloads taking seeds as large as
+/// those of scalar functions declare `UBIGINT`, and the others `BIGINT`.
+pub fn validate_seed_param(
+    input: &AggregateInput,
+    col_idx: usize,
+    param_name: &str,
+) -> Result<u64, Box<dyn Error>> {
+    let type_id = unsafe {
+        let vector = ffi::duckdb_data_chunk_get_vector(input.ptr, col_idx as u64);
+        let mut logical_type = ffi::duckdb_vector_get_column_type(vector);
+        let type_id = ffi::duckdb_get_type_id(logical_type);
+        ffi::duckdb_destroy_logical_type(&mut logical_type);
+        type_id
+    };
+    if type_id == LogicalTypeId::UBigint as ffi::DUCKDB_TYPE {
+        validate_constant_param(
+            input
+                .flat_vector(col_idx)
+                .as_slice_with_len::<u64>(input.len()),
+            param_name,
+        )
+    } else {
+        Ok(validate_count_param(input, col_idx, param_name)? as u64)
+    }
+}
+
+/// Duckdb aggregate function trait
+///
+/// Each group owns a boxed `State`, created with `Default` and dropped by DuckDB
+/// through the registered destructor.
+pub trait VAggregate {
+    type State: Default;
+
+    /// Adds each row of `input` to its group state, `states[row_idx]`.
+    ///
+    /// # Safety
+    ///
+    /// Several rows may share a state pointer, so implementations must not hold
+    /// references to more than one state at a time.
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Merges `source` into `target`.
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>>;
+
+    /// Writes the result of `states[i]` to row `offset + i` of `output`.
+    ///
+    /// # Safety
+    ///
+    /// `output` must be a vector of the signature's return type.
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// The possible signatures of the aggregate function.
+    fn signatures() -> Vec<AggregateFunctionSignature>;
+}
+
+unsafe fn set_error(info: ffi::duckdb_function_info, error: &dyn Error) {
+    let message = CString::new(error.to_string())
+        .unwrap_or_else(|_| CString::new("Aggregate function failed").unwrap());
+    ffi::duckdb_aggregate_function_set_error(info, message.as_ptr());
+}
+
+#[inline]
+unsafe fn state_ptr<T: VAggregate>(state: ffi::duckdb_aggregate_state) -> *mut T::State {
+    *(state as *mut *mut T::State)
+}
+
+unsafe extern "C" fn state_size<T: VAggregate>(_: ffi::duckdb_function_info) -> ffi::idx_t {
+    std::mem::size_of::<*mut T::State>() as ffi::idx_t
+}
+
+unsafe extern "C" fn state_init<T: VAggregate>(
+    _: ffi::duckdb_function_info,
+    state: ffi::duckdb_aggregate_state,
+) {
+    *(state as *mut *mut T::State) = Box::into_raw(Box::default());
+}
+
+unsafe extern "C" fn state_destroy<T: VAggregate>(
+    states: *mut ffi::duckdb_aggregate_state,
+    count: ffi::idx_t,
+) {
+    for &state in slice::from_raw_parts(states, count as usize) {
+        let inner = state as *mut *mut T::State;
+        if !(*inner).is_null() {
+            drop(Box::from_raw(*inner));
+            *inner = ptr::null_mut();
+        }
+    }
+}
+
+unsafe extern "C" fn update<T: VAggregate>(
+    info: ffi::duckdb_function_info,
+    input: ffi::duckdb_data_chunk,
+    states: *mut ffi::duckdb_aggregate_state,
+) {
+    let input = AggregateInput { ptr: input };
+    let states: Vec<*mut T::State> = slice::from_raw_parts(states, input.len())
+        .iter()
+        .map(|&state| state_ptr::<T>(state))
+        .collect();
+    if let Err(e) = T::update(&input, &states) {
+        set_error(info, e.as_ref());
+    }
+}
+
+unsafe extern "C" fn combine<T: VAggregate>(
+    info: ffi::duckdb_function_info,
+    source: *mut ffi::duckdb_aggregate_state,
+    target: *mut ffi::duckdb_aggregate_state,
+    count: ffi::idx_t,
+) {
+    let sources = slice::from_raw_parts(source, count as usize);
+    let targets = slice::from_raw_parts(target, count as usize);
+    for (&source, &target) in sources.iter().zip(targets) {
+        let result = T::combine(&mut *state_ptr::<T>(source), &mut *state_ptr::<T>(target));
+        if let Err(e) = result {
+            set_error(info, e.as_ref());
+            return;
+        }
+    }
+}
+
+unsafe extern "C" fn finalize<T: VAggregate>(
+    info: ffi::duckdb_function_info,
+    source: *mut ffi::duckdb_aggregate_state,
+    mut result: ffi::duckdb_vector,
+    count: ffi::idx_t,
+    offset: ffi::idx_t,
+) {
+    let mut states: Vec<&mut T::State> = slice::from_raw_parts(source, count as usize)
+        .iter()
+        .map(|&state| &mut *state_ptr::<T>(state))
+        .collect();
+    if let Err(e) = T::finalize(&mut states, offset as usize, &mut result) {
+        set_error(info, e.as_ref());
+    }
+}
+
+/// Registers `T` under `name`, with one overload per signature.
+///
+/// # Safety
+///
+/// `con` must be an open connection.
+pub unsafe fn register_aggregate_function<T: VAggregate>(
+    con: ffi::duckdb_connection,
+    name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let c_name = CString::new(name)?;
+    let mut function_set = ffi::duckdb_create_aggregate_function_set(c_name.as_ptr());
+
+    for signature in T::signatures() {
+        let mut function = ffi::duckdb_create_aggregate_function();
+        ffi::duckdb_aggregate_function_set_name(function, c_name.as_ptr());
+        for parameter in &signature.parameters {
+            let mut logical_type = parameter.create()?;
+            ffi::duckdb_aggregate_function_add_parameter(function, logical_type);
+            ffi::duckdb_destroy_logical_type(&mut logical_type);
+        }
+        let mut return_type = signature.return_type.create()?;
+        ffi::duckdb_aggregate_function_set_return_type(function, return_type);
+        ffi::duckdb_destroy_logical_type(&mut return_type);
+
+        ffi::duckdb_aggregate_function_set_functions(
+            function,
+            Some(state_size::<T>),
+            Some(state_init::<T>),
+            Some(update::<T>),
+            Some(combine::<T>),
+            Some(finalize::<T>),
+        );
+        ffi::duckdb_aggregate_function_set_destructor(function, Some(state_destroy::<T>));
+
+        ffi::duckdb_add_aggregate_function_to_set(function_set, function);
+        ffi::duckdb_destroy_aggregate_function(&mut function);
+    }
+
+    let rc = ffi::duckdb_register_aggregate_function_set(con, function_set);
+    ffi::duckdb_destroy_aggregate_function_set(&mut function_set);
+    if rc != ffi::DuckDBSuccess {
+        return Err(format!("Failed to register {} function", name).into());
+    }
+    Ok(())
+}
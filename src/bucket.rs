@@ -0,0 +1,276 @@
+use std::cmp::Reverse;
+use std::error::Error;
+
+use rustc_hash::FxHashMap;
+
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+    Result,
+};
+
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+use super::{mix, validate_constant_param};
+
+/// Whether the row `key` stays in its bucket of `bucket_size` rows when the
+/// bucket is subsampled to `max_bucket_size` rows on average.
+///
+/// Every row of an oversized bucket is kept with probability
+/// `max_bucket_size / bucket_size`, decided by a hash of the bucket, the row
+/// and `seed` alone, so the same rows are kept whatever their order and in
+/// every table the bucket is joined from.
+fn keep_row(
+    band: u64,
+    band_key: u64,
+    key: u64,
+    bucket_size: u64,
+    max_bucket_size: u64,
+    seed: u64,
+) -> bool {
+    if bucket_size <= max_bucket_size {
+        return true;
+    }
+    let hash = mix(seed ^ mix(band ^ mix(band_key ^ mix(key))));
+    // Maps the hash uniformly onto 0..bucket_size
+    (((hash as u128) * (bucket_size as u128)) >> 64) < max_bucket_size as u128
+}
+
+/// Bucket sizes of the band keys seen, with the size limit they are checked
+/// against.
+#[derive(Debug, Default)]
+pub struct StopBucketsState {
+    max_bucket_size: Option<usize>,
+    sizes: FxHashMap<(u64, u64), u64>,
+}
+
+impl StopBucketsState {
+    fn set_max_bucket_size(&mut self, max_bucket_size: usize) -> Result<(), Box<dyn Error>> {
+        match self.max_bucket_size {
+            None => self.max_bucket_size = Some(max_bucket_size),
+            Some(current) if current != max_bucket_size => {
+                return Err("max_bucket_size must be the same for every row".into())
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct StopBuckets {}
+
+impl VAggregate for StopBuckets {
+    type State = StopBucketsState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `band` and `band_key` inputs
+        let input_bands = input.flat_vector(0);
+        let bands = input_bands.as_slice_with_len::<u64>(input.len());
+        let input_band_keys = input.flat_vector(1);
+        let band_keys = input_band_keys.as_slice_with_len::<u64>(input.len());
+
+        // Prepare `max_bucket_size` input
+        let max_bucket_size = validate_count_param(input, 2, "max_bucket_size")?;
+
+        // Count the rows of each bucket
+        for (row_idx, (&band, &band_key)) in bands.iter().zip(band_keys).enumerate() {
+            if input_bands.row_is_null(row_idx as u64)
+                || input_band_keys.row_is_null(row_idx as u64)
+            {
+                continue; // Skip to the next row
+            }
+            let state = &mut *states[row_idx];
+            state.set_max_bucket_size(max_bucket_size)?;
+            *state.sizes.entry((band, band_key)).or_default() += 1;
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(max_bucket_size) = source.max_bucket_size {
+            target.set_max_bucket_size(max_bucket_size)?;
+            for (bucket, size) in source.sizes.drain() {
+                *target.sizes.entry(bucket).or_default() += size;
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Oversized buckets of each group, largest first
+        let row_buckets: Vec<Option<Vec<(u64, u64, u64)>>> = states
+            .iter()
+            .map(|state| {
+                let max_bucket_size = state.max_bucket_size? as u64;
+                let mut buckets: Vec<(u64, u64, u64)> = state
+                    .sizes
+                    .iter()
+                    .filter(|(_, &size)| size > max_bucket_size)
+                    .map(|(&(band, band_key), &size)| (band, band_key, size))
+                    .collect();
+                buckets.sort_unstable_by_key(|&(band, band_key, size)| {
+                    (Reverse(size), band, band_key)
+                });
+                Some(buckets)
+            })
+            .collect();
+
+        // Prepare output, after the entries of earlier finalized states
+        let mut output_buckets = output.list_vector();
+        let buckets_offset = output_buckets.len();
+        let buckets_len_sum: usize =
+            buckets_offset + row_buckets.iter().flatten().map(|b| b.len()).sum::<usize>();
+        let buckets_vec = output_buckets.struct_child(buckets_len_sum);
+        let mut bands_vec = buckets_vec.child(0, buckets_len_sum);
+        let bands: &mut [u64] = bands_vec.as_mut_slice_with_len(buckets_len_sum);
+        let mut band_keys_vec = buckets_vec.child(1, buckets_len_sum);
+        let band_keys: &mut [u64] = band_keys_vec.as_mut_slice_with_len(buckets_len_sum);
+        let mut sizes_vec = buckets_vec.child(2, buckets_len_sum);
+        let sizes: &mut [i64] = sizes_vec.as_mut_slice_with_len(buckets_len_sum);
+
+        // Write output
+        let mut bucket_offset = buckets_offset;
+        for (state_idx, buckets) in row_buckets.iter().enumerate() {
+            let Some(buckets) = buckets else {
+                output_buckets.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            for (bucket_idx, &(band, band_key, size)) in buckets.iter().enumerate() {
+                bands[bucket_offset + bucket_idx] = band;
+                band_keys[bucket_offset + bucket_idx] = band_key;
+                sizes[bucket_offset + bucket_idx] = size as i64;
+            }
+            output_buckets.set_entry(offset + state_idx, bucket_offset, buckets.len());
+            bucket_offset += buckets.len();
+        }
+        output_buckets.set_len(bucket_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        // Bands are numbered by `generate_subscripts` as BIGINT and by
+        // `lsh_index_keys` as UBIGINT, and are returned as they are given
+        vec![
+            AggregateFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                ],
+                AggregateType::list(AggregateType::struct_type(vec![
+                    ("band", LogicalTypeId::Bigint.into()),
+                    ("band_key", LogicalTypeId::UBigint.into()),
+                    ("size", LogicalTypeId::Bigint.into()),
+                ])),
+            ),
+            AggregateFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                ],
+                AggregateType::list(AggregateType::struct_type(vec![
+                    ("band", LogicalTypeId::UBigint.into()),
+                    ("band_key", LogicalTypeId::UBigint.into()),
+                    ("size", LogicalTypeId::Bigint.into()),
+                ])),
+            ),
+        ]
+    }
+}
+
+pub struct BucketSample {}
+
+impl VScalar for BucketSample {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `band`, `band_key`, `key` and `bucket_size` inputs
+        let input_bands = input.flat_vector(0);
+        let bands = input_bands.as_slice_with_len::<u64>(input.len());
+        let input_band_keys = input.flat_vector(1);
+        let band_keys = input_band_keys.as_slice_with_len::<u64>(input.len());
+        let input_keys = input.flat_vector(2);
+        let keys = input_keys.as_slice_with_len::<u64>(input.len());
+        let input_bucket_sizes = input.flat_vector(3);
+        let bucket_sizes = input_bucket_sizes.as_slice_with_len::<i64>(input.len());
+
+        // Prepare `max_bucket_size` input
+        let max_bucket_size = validate_constant_param(
+            input.flat_vector(4).as_slice_with_len::<u64>(input.len()),
+            "max_bucket_size",
+        )?;
+
+        // Prepare `seed` input
+        let seed = validate_constant_param(
+            input.flat_vector(5).as_slice_with_len::<u64>(input.len()),
+            "seed",
+        )?;
+
+        // Decide for each row whether it stays in its bucket
+        let mut output_keeps = output.flat_vector();
+        for row_idx in 0..input.len() {
+            if input_bands.row_is_null(row_idx as u64)
+                || input_band_keys.row_is_null(row_idx as u64)
+                || input_keys.row_is_null(row_idx as u64)
+                || input_bucket_sizes.row_is_null(row_idx as u64)
+            {
+                output_keeps.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+            let keeps = output_keeps.as_mut_slice_with_len::<bool>(input.len());
+            keeps[row_idx] = keep_row(
+                bands[row_idx],
+                band_keys[row_idx],
+                keys[row_idx],
+                bucket_sizes[row_idx].max(0) as u64,
+                max_bucket_size,
+                seed,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Boolean.into(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::Bigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Boolean.into(),
+            ),
+        ]
+    }
+}
@@ -0,0 +1,111 @@
+use std::error::Error;
+
+use duckdb::{core::LogicalTypeId, vtab::arrow::WritableVector};
+
+use super::aggregate::{AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate};
+
+pub mod union_find;
+
+use union_find::UnionFind;
+
+pub struct ConnectedComponents {}
+
+impl VAggregate for ConnectedComponents {
+    type State = UnionFind;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `left` and `right` node inputs
+        let input_lefts = input.flat_vector(0);
+        let lefts = input_lefts.as_slice_with_len::<i64>(input.len());
+        let input_rights = input.flat_vector(1);
+        let rights = input_rights.as_slice_with_len::<i64>(input.len());
+
+        // Join the nodes of each pair, keeping nodes paired with NULL as singletons
+        for (row_idx, (&left, &right)) in lefts.iter().zip(rights).enumerate() {
+            let state = &mut *states[row_idx];
+            match (
+                input_lefts.row_is_null(row_idx as u64),
+                input_rights.row_is_null(row_idx as u64),
+            ) {
+                (false, false) => state.union(left, right),
+                (false, true) => {
+                    state.insert(left);
+                }
+                (true, false) => {
+                    state.insert(right);
+                }
+                (true, true) => continue, // Skip to the next row
+            }
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        target.merge(std::mem::take(source));
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let row_components: Vec<Option<Vec<(i64, i64)>>> = states
+            .iter_mut()
+            .map(|state| {
+                if state.is_empty() {
+                    None
+                } else {
+                    Some(std::mem::take(&mut **state).components())
+                }
+            })
+            .collect();
+
+        // Prepare output, after the entries of earlier finalized states
+        let mut output_components = output.list_vector();
+        let components_offset = output_components.len();
+        let components_len_sum: usize = components_offset
+            + row_components
+                .iter()
+                .flatten()
+                .map(|c| c.len())
+                .sum::<usize>();
+        let components_vec = output_components.struct_child(components_len_sum);
+        let mut nodes_vec = components_vec.child(0, components_len_sum);
+        let nodes: &mut [i64] = nodes_vec.as_mut_slice_with_len(components_len_sum);
+        let mut cluster_ids_vec = components_vec.child(1, components_len_sum);
+        let cluster_ids: &mut [i64] = cluster_ids_vec.as_mut_slice_with_len(components_len_sum);
+
+        // Write output
+        let mut component_offset = components_offset;
+        for (state_idx, components) in row_components.iter().enumerate() {
+            let Some(components) = components else {
+                output_components.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            for (component_idx, &(node, cluster_id)) in components.iter().enumerate() {
+                nodes[component_offset + component_idx] = node;
+                cluster_ids[component_offset + component_idx] = cluster_id;
+            }
+            output_components.set_entry(offset + state_idx, component_offset, components.len());
+            component_offset += components.len();
+        }
+        output_components.set_len(component_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![LogicalTypeId::Bigint.into(), LogicalTypeId::Bigint.into()],
+            AggregateType::list(AggregateType::struct_type(vec![
+                ("node", LogicalTypeId::Bigint.into()),
+                ("cluster_id", LogicalTypeId::Bigint.into()),
+            ])),
+        )]
+    }
+}
@@ -0,0 +1,76 @@
+use rustc_hash::FxHashMap;
+
+/// Disjoint sets of node ids, merged with union by size and path halving.
+#[derive(Debug, Default)]
+pub struct UnionFind {
+    indices: FxHashMap<i64, usize>,
+    nodes: Vec<i64>,
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds `node` as a singleton if it is not in any set yet.
+    pub fn insert(&mut self, node: i64) -> usize {
+        if let Some(&idx) = self.indices.get(&node) {
+            return idx;
+        }
+        let idx = self.nodes.len();
+        self.indices.insert(node, idx);
+        self.nodes.push(node);
+        self.parents.push(idx);
+        self.sizes.push(1);
+        idx
+    }
+
+    fn find(&mut self, mut idx: usize) -> usize {
+        while self.parents[idx] != idx {
+            self.parents[idx] = self.parents[self.parents[idx]];
+            idx = self.parents[idx];
+        }
+        idx
+    }
+
+    /// Merges the sets containing `a` and `b`, adding either if needed.
+    pub fn union(&mut self, a: i64, b: i64) {
+        let a_idx = self.insert(a);
+        let b_idx = self.insert(b);
+        let (mut a_root, mut b_root) = (self.find(a_idx), self.find(b_idx));
+        if a_root == b_root {
+            return;
+        }
+        if self.sizes[a_root] < self.sizes[b_root] {
+            std::mem::swap(&mut a_root, &mut b_root);
+        }
+        self.parents[b_root] = a_root;
+        self.sizes[a_root] += self.sizes[b_root];
+    }
+
+    /// Merges every set of `other` into this one.
+    pub fn merge(&mut self, mut other: Self) {
+        for idx in 0..other.nodes.len() {
+            let root = other.find(idx);
+            self.union(other.nodes[idx], other.nodes[root]);
+        }
+    }
+
+    /// Every node with the smallest node of its set as its cluster id, ordered
+    /// by node.
+    pub fn components(mut self) -> Vec<(i64, i64)> {
+        let mut cluster_ids: FxHashMap<usize, i64> = FxHashMap::default();
+        for idx in 0..self.nodes.len() {
+            let root = self.find(idx);
+            let cluster_id = cluster_ids.entry(root).or_insert(self.nodes[idx]);
+            *cluster_id = (*cluster_id).min(self.nodes[idx]);
+        }
+        let mut components: Vec<(i64, i64)> = (0..self.nodes.len())
+            .map(|idx| (self.nodes[idx], cluster_ids[&self.find(idx)]))
+            .collect();
+        components.sort_unstable();
+        components
+    }
+}
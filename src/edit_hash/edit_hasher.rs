@@ -0,0 +1,73 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+use ndarray_rand::rand_distr::Uniform;
+
+use rand::Rng;
+
+/// Edit-distance LSH: strings are embedded into Hamming space with the CGK
+/// random walk (Chakraborty, Goldenberg & Koucký, 2016), under which strings
+/// within edit distance `K` have embeddings within Hamming distance `O(K²)`
+/// with good probability, and each hash samples a band of embedding positions.
+#[derive(Debug)]
+pub struct EditHasher {
+    walk_seed: u64,
+    positions: Vec<usize>,
+    max_length: usize,
+}
+
+impl EditHasher {
+    pub fn new<R: Rng>(band_width: usize, max_length: usize, rand_state: &mut R) -> Self {
+        let walk_seed = rand_state.gen();
+        let dist = Uniform::new(0, (3 * max_length).max(1));
+        let positions = (0..band_width).map(|_| rand_state.sample(dist)).collect();
+        Self {
+            walk_seed,
+            positions,
+            max_length,
+        }
+    }
+
+    pub fn hash(&self, embedding: &[Option<char>]) -> u64 {
+        let mut hasher = FxHasher::default();
+        for &position in &self.positions {
+            embedding[position].hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// CGK embedding of the first `max_length` characters of `text`, of length
+    /// `3 * max_length` and padded with `None`.
+    ///
+    /// At each step the current character is copied to the output, and the walk
+    /// advances to the next character if the random bit for that step and
+    /// character is set, so strings that agree after an edit soon realign.
+    pub fn embed(&self, text: &str) -> Vec<Option<char>> {
+        let chars: Vec<char> = text.chars().take(self.max_length).collect();
+        let mut embedding = Vec::with_capacity(3 * self.max_length);
+        let mut char_idx = 0;
+        for step in 0..3 * self.max_length {
+            match chars.get(char_idx) {
+                Some(&c) => {
+                    embedding.push(Some(c));
+                    if self.advance(step, c) {
+                        char_idx += 1;
+                    }
+                }
+                None => embedding.push(None),
+            }
+        }
+        embedding
+    }
+
+    #[inline]
+    fn advance(&self, step: usize, c: char) -> bool {
+        let mut hasher = FxHasher::default();
+
+        self.walk_seed.hash(&mut hasher);
+        step.hash(&mut hasher);
+        c.hash(&mut hasher);
+
+        hasher.finish() >> 63 == 1
+    }
+}
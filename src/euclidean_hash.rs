@@ -1,21 +1,37 @@
 use std::error::Error;
 
+use ndarray::{ArrayView1, ArrayView2};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
-use duckdb::ffi::duckdb_list_entry;
+use duckdb::ffi::{duckdb_list_entry, duckdb_string_t};
+use duckdb::types::DuckString;
 use duckdb::{
-    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
     vscalar::{ScalarFunctionSignature, VScalar},
     vtab::arrow::WritableVector,
     Result,
 };
 
-use super::{validate_constant_param, HashOutput};
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+use super::{validate_blob_len, validate_constant_param, BlobCache, HashOutput};
 
+pub mod crosspolytope_hasher;
 pub mod euclidean_hasher;
+pub mod geo_hasher;
+pub mod knn;
+pub mod learned_hasher;
+pub mod mips;
+pub mod sparse_hasher;
 
+use crosspolytope_hasher::CrossPolytopeHasher;
 use euclidean_hasher::EuclideanHasher;
+use geo_hasher::GeoHasher;
+use knn::{band_hashers, KnnIndex, Metric};
+use learned_hasher::LearnedHasher;
+use sparse_hasher::{CosineHasher, SparseEuclideanHasher, SparseVector};
 
 unsafe fn euclidean_hash_invoke_generic<T: HashOutput>(
     input: &mut DataChunkHandle,
@@ -97,54 +113,1546 @@ unsafe fn euclidean_hash_invoke_generic<T: HashOutput>(
     Ok(())
 }
 
-pub struct EuclideanHash {}
+unsafe fn euclidean_probes_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare coordinate array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
 
-impl VScalar for EuclideanHash {
-    type State = ();
+    // Validate coordinate array input
+    let mut array_size: Option<u64> = None;
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            continue; // Skip to the next row
+        }
+        match array_size {
+            None => array_size = Some(meta.length),
+            Some(size) => {
+                if size != meta.length {
+                    return Err("All input arrays must have the same length".into());
+                }
+            }
+        }
+    }
 
-    unsafe fn invoke(
-        _: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn Error>> {
-        euclidean_hash_invoke_generic::<u64>(input, output)
+    // Prepare `bucket_width` input
+    let bucket_width = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<f64>(input.len()),
+        "bucket_width",
+    )?;
+
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(4).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare `num_probes` input
+    let num_probes = validate_constant_param(
+        input.flat_vector(5).as_slice_with_len::<usize>(input.len()),
+        "num_probes",
+    )?;
+
+    // Perform hashing, keeping each band's bucket followed by its probes
+    let mut row_probes: Vec<Option<Vec<Vec<u64>>>> = Vec::with_capacity(input.len());
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            row_probes.push(None);
+            continue; // Skip to the next row
+        }
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        let mut rng = StdRng::seed_from_u64(seed);
+        let band_probes = (0..band_count)
+            .map(|_| {
+                let hasher = EuclideanHasher::new(bucket_width, band_size, arr_length, &mut rng);
+                hasher.probe_hashes(arr.into(), num_probes)
+            })
+            .collect();
+        row_probes.push(Some(band_probes));
     }
 
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
-                LogicalTypeId::Double.into(),
-                LogicalTypeId::UBigint.into(),
-                LogicalTypeId::UBigint.into(),
-                LogicalTypeId::UBigint.into(),
-            ],
-            LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
-        )]
+    // Prepare output
+    let mut output_bands = output.list_vector();
+    let bands_len_sum: usize = row_probes.iter().flatten().map(|bands| bands.len()).sum();
+    let mut band_entries_vec = output_bands.child(bands_len_sum);
+    let band_entries: &mut [duckdb_list_entry] =
+        band_entries_vec.as_mut_slice_with_len(bands_len_sum);
+    let output_hashes = output_bands.list_child();
+    let hashes_len_sum: usize = row_probes
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|probes| probes.len())
+        .sum();
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Write output
+    let mut band_offset = 0;
+    let mut hash_offset = 0;
+    for (row_idx, bands) in row_probes.iter().enumerate() {
+        let Some(bands) = bands else {
+            output_bands.set_null(row_idx);
+            continue; // Skip to the next row
+        };
+        for (band_idx, probes) in bands.iter().enumerate() {
+            for (probe_idx, &probe) in probes.iter().enumerate() {
+                hashes[hash_offset + probe_idx] = T::from_u64(probe);
+            }
+            band_entries[band_offset + band_idx] = duckdb_list_entry {
+                offset: hash_offset as u64,
+                length: probes.len() as u64,
+            };
+            hash_offset += probes.len();
+        }
+        output_bands.set_entry(row_idx, band_offset, bands.len());
+        band_offset += bands.len();
     }
+    output_bands.set_len(band_offset);
+    output_hashes.set_len(hash_offset);
+
+    Ok(())
 }
 
-pub struct EuclideanHash32 {}
+unsafe fn crosspolytope_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare coordinate array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
 
-impl VScalar for EuclideanHash32 {
-    type State = ();
+    // Validate coordinate array input
+    let mut array_size: Option<u64> = None;
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            continue; // Skip to the next row
+        }
+        match array_size {
+            None => array_size = Some(meta.length),
+            Some(size) => {
+                if size != meta.length {
+                    return Err("All input arrays must have the same length".into());
+                }
+            }
+        }
+    }
 
-    unsafe fn invoke(
-        _: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn Error>> {
-        euclidean_hash_invoke_generic::<u32>(input, output)
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Perform hashing, with the same rotations for every row
+    let mut rng = StdRng::seed_from_u64(seed);
+    let hashers: Vec<CrossPolytopeHasher> = (0..band_count)
+        .map(|_| CrossPolytopeHasher::new(band_size, array_size.unwrap_or(0) as usize, &mut rng))
+        .collect();
+    let mut hash_offset = 0;
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        for (band_idx, hasher) in hashers.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(arr.into()));
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_count);
+        hash_offset += band_count;
     }
+    output_hashes.set_len(hash_offset); // Corrects initial estimate if NULLs exist
 
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
+    Ok(())
+}
+
+unsafe fn geo_hash_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare `lat` and `lon` inputs
+    let input_lats = input.flat_vector(0);
+    let lats = input_lats.as_slice_with_len::<f64>(input.len());
+    let input_lons = input.flat_vector(1);
+    let lons = input_lons.as_slice_with_len::<f64>(input.len());
+
+    // Prepare `radius_km` input
+    let radius_km = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<f64>(input.len()),
+        "radius_km",
+    )?;
+    if radius_km <= 0.0 {
+        return Err("radius_km must be positive".into());
+    }
+
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input.flat_vector(4).as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(5).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Perform hashing, with the same grids for every row
+    let mut rng = StdRng::seed_from_u64(seed);
+    let hashers: Vec<GeoHasher> = (0..band_count)
+        .map(|_| GeoHasher::new(radius_km, band_size, &mut rng))
+        .collect();
+    let mut hash_offset = 0;
+    for (row_idx, (&lat, &lon)) in lats.iter().zip(lons).enumerate() {
+        if input_lats.row_is_null(row_idx as u64) || input_lons.row_is_null(row_idx as u64) {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err("Latitudes must be between -90 and 90 degrees".into());
+        }
+        let point = geo_hasher::to_cartesian(lat, lon);
+        for (band_idx, hasher) in hashers.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(&point));
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_count);
+        hash_offset += band_count;
+    }
+    output_hashes.set_len(hash_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+/// Logical types of sparse vectors, `MAP(INTEGER, DOUBLE)` and
+/// `STRUCT(indices INTEGER[], values DOUBLE[])`.
+fn sparse_vector_types() -> Vec<LogicalTypeHandle> {
+    vec![
+        LogicalTypeHandle::map(
+            &LogicalTypeId::Integer.into(),
+            &LogicalTypeId::Double.into(),
+        ),
+        LogicalTypeHandle::struct_type(&[
+            (
+                "indices",
+                LogicalTypeHandle::list(&LogicalTypeId::Integer.into()),
+            ),
+            (
+                "values",
                 LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
-                LogicalTypeId::Double.into(),
-                LogicalTypeId::UBigint.into(),
-                LogicalTypeId::UBigint.into(),
-                LogicalTypeId::UBigint.into(),
+            ),
+        ]),
+    ]
+}
+
+/// Reads column 0 as `(index, value)` pairs, where the elements of a dense
+/// array have the indices 1, 2, 3, ... and `NULL` values are left out.
+unsafe fn sparse_vectors(
+    input: &DataChunkHandle,
+) -> Result<Vec<Option<SparseVector>>, Box<dyn Error>> {
+    let input_vectors = input.flat_vector(0);
+    let mut vectors = Vec::with_capacity(input.len());
+    match input_vectors.logical_type().id() {
+        LogicalTypeId::Map => {
+            // Prepare map input
+            let input_maps_data = input.list_vector(0);
+            let maps_meta = input_vectors.as_slice_with_len::<duckdb_list_entry>(input.len());
+            let entries = input_maps_data.struct_child(input_maps_data.len());
+            let keys_vec = entries.child(0, input_maps_data.len());
+            let keys: &[i32] = keys_vec.as_slice_with_len(input_maps_data.len());
+            let values_vec = entries.child(1, input_maps_data.len());
+            let values: &[f64] = values_vec.as_slice_with_len(input_maps_data.len());
+
+            for (row_idx, meta) in maps_meta.iter().enumerate() {
+                if input_vectors.row_is_null(row_idx as u64) {
+                    vectors.push(None);
+                    continue; // Skip to the next row
+                }
+                let map_offset = meta.offset as usize;
+                let map_length = meta.length as usize;
+                let vector = (map_offset..(map_offset + map_length))
+                    .filter(|&entry_idx| !values_vec.row_is_null(entry_idx as u64))
+                    .map(|entry_idx| (keys[entry_idx] as i64, values[entry_idx]))
+                    .collect();
+                vectors.push(Some(vector));
+            }
+        }
+        LogicalTypeId::Struct => {
+            // Prepare `indices` and `values` inputs
+            let input_structs = input.struct_vector(0);
+            let indices_meta_vec = input_structs.child(0, input.len());
+            let indices_meta = indices_meta_vec.as_slice_with_len::<duckdb_list_entry>(input.len());
+            let indices_data = input_structs.list_vector_child(0);
+            let indices_vec = indices_data.child(indices_data.len());
+            let indices: &[i32] = indices_vec.as_slice_with_len(indices_data.len());
+            let values_meta_vec = input_structs.child(1, input.len());
+            let values_meta = values_meta_vec.as_slice_with_len::<duckdb_list_entry>(input.len());
+            let values_data = input_structs.list_vector_child(1);
+            let values_vec = values_data.child(values_data.len());
+            let values: &[f64] = values_vec.as_slice_with_len(values_data.len());
+
+            for (row_idx, (index_meta, value_meta)) in
+                indices_meta.iter().zip(values_meta).enumerate()
+            {
+                if input_vectors.row_is_null(row_idx as u64)
+                    || indices_meta_vec.row_is_null(row_idx as u64)
+                    || values_meta_vec.row_is_null(row_idx as u64)
+                {
+                    vectors.push(None);
+                    continue; // Skip to the next row
+                }
+                if index_meta.length != value_meta.length {
+                    return Err("indices and values must have the same length".into());
+                }
+                let vector = (0..index_meta.length as usize)
+                    .map(|entry_idx| {
+                        (
+                            index_meta.offset as usize + entry_idx,
+                            value_meta.offset as usize + entry_idx,
+                        )
+                    })
+                    .filter(|&(index_idx, value_idx)| {
+                        !indices_vec.row_is_null(index_idx as u64)
+                            && !values_vec.row_is_null(value_idx as u64)
+                    })
+                    .map(|(index_idx, value_idx)| (indices[index_idx] as i64, values[value_idx]))
+                    .collect();
+                vectors.push(Some(vector));
+            }
+        }
+        _ => {
+            // Prepare coordinate array input
+            let input_arrays_data = input.list_vector(0);
+            let arrays_meta = input_vectors.as_slice_with_len::<duckdb_list_entry>(input.len());
+            let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+            let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+            for (row_idx, meta) in arrays_meta.iter().enumerate() {
+                if input_vectors.row_is_null(row_idx as u64) {
+                    vectors.push(None);
+                    continue; // Skip to the next row
+                }
+                let arr_offset = meta.offset as usize;
+                let arr_length = meta.length as usize;
+                let vector = (arr_offset..(arr_offset + arr_length))
+                    .filter(|&value_idx| !arrays_vec.row_is_null(value_idx as u64))
+                    .map(|value_idx| ((value_idx - arr_offset + 1) as i64, arrays[value_idx]))
+                    .collect();
+                vectors.push(Some(vector));
+            }
+        }
+    }
+    Ok(vectors)
+}
+
+unsafe fn sparse_euclidean_hash_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare sparse vector input
+    let vectors = sparse_vectors(input)?;
+
+    // Prepare `bucket_width` input
+    let bucket_width = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<f64>(input.len()),
+        "bucket_width",
+    )?;
+
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(4).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Perform hashing
+    let hashers: Vec<SparseEuclideanHasher> = (0..band_count)
+        .map(|band_idx| SparseEuclideanHasher::new(bucket_width, seed, band_idx, band_size))
+        .collect();
+    let mut hash_offset = 0;
+    for (row_idx, vector) in vectors.iter().enumerate() {
+        let Some(vector) = vector else {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        };
+        for (band_idx, hasher) in hashers.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(vector));
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_count);
+        hash_offset += band_count;
+    }
+    output_hashes.set_len(hash_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+unsafe fn cosine_hash_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare vector input
+    let vectors = sparse_vectors(input)?;
+
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Perform hashing
+    let hashers: Vec<CosineHasher> = (0..band_count)
+        .map(|band_idx| CosineHasher::new(seed, band_idx, band_size))
+        .collect();
+    let mut hash_offset = 0;
+    for (row_idx, vector) in vectors.iter().enumerate() {
+        let Some(vector) = vector else {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        };
+        for (band_idx, hasher) in hashers.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(vector));
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_count);
+        hash_offset += band_count;
+    }
+    output_hashes.set_len(hash_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+/// Hashes item vectors, when `items` is set, or query vectors with Simple-LSH,
+/// so that items share band hashes with the queries they have large inner
+/// products with.
+unsafe fn mips_hash_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    items: bool,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare vector input
+    let vectors = sparse_vectors(input)?;
+
+    // Prepare `max_norm` input, which only items have
+    let param_offset = if items { 2 } else { 1 };
+    let max_norm = if items {
+        let max_norm = validate_constant_param(
+            input.flat_vector(1).as_slice_with_len::<f64>(input.len()),
+            "max_norm",
+        )?;
+        if max_norm <= 0.0 {
+            return Err("max_norm must be positive".into());
+        }
+        max_norm
+    } else {
+        1.0
+    };
+
+    // Prepare `band_count` input
+    let band_count = validate_constant_param(
+        input
+            .flat_vector(param_offset)
+            .as_slice_with_len::<usize>(input.len()),
+        "band_count",
+    )?;
+
+    // Prepare `band_size` input
+    let band_size = validate_constant_param(
+        input
+            .flat_vector(param_offset + 1)
+            .as_slice_with_len::<usize>(input.len()),
+        "band_size",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input
+            .flat_vector(param_offset + 2)
+            .as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Perform hashing of the transformed vectors
+    let hashers: Vec<CosineHasher> = (0..band_count)
+        .map(|band_idx| CosineHasher::new(seed, band_idx, band_size))
+        .collect();
+    let mut hash_offset = 0;
+    for (row_idx, vector) in vectors.iter().enumerate() {
+        let Some(vector) = vector else {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        };
+        let vector = if items {
+            mips::item_transform(vector, max_norm)?
+        } else {
+            mips::query_transform(vector)
+        };
+        for (band_idx, hasher) in hashers.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(&vector));
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_count);
+        hash_offset += band_count;
+    }
+    output_hashes.set_len(hash_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+pub struct EuclideanHash {}
+
+impl VScalar for EuclideanHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::List => euclidean_hash_invoke_generic::<u64>(input, output),
+            LogicalTypeId::Map | LogicalTypeId::Struct => {
+                sparse_euclidean_hash_invoke_generic::<u64>(input, output)
+            }
+            _ => Err("Unsupported argument type for euclidean hashing".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::Double.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct EuclideanHash32 {}
+
+impl VScalar for EuclideanHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::List => euclidean_hash_invoke_generic::<u32>(input, output),
+            LogicalTypeId::Map | LogicalTypeId::Struct => {
+                sparse_euclidean_hash_invoke_generic::<u32>(input, output)
+            }
+            _ => Err("Unsupported argument type for euclidean hashing".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::Double.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct EuclideanProbes {}
+
+impl VScalar for EuclideanProbes {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        euclidean_probes_invoke_generic::<u64>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeHandle::list(&LogicalTypeId::UBigint.into())),
+        )]
+    }
+}
+
+pub struct EuclideanProbes32 {}
+
+impl VScalar for EuclideanProbes32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        euclidean_probes_invoke_generic::<u32>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeHandle::list(&LogicalTypeId::UInteger.into())),
+        )]
+    }
+}
+
+pub struct CrossPolytopeHash {}
+
+impl VScalar for CrossPolytopeHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        crosspolytope_invoke_generic::<u64>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+        )]
+    }
+}
+
+pub struct CrossPolytopeHash32 {}
+
+impl VScalar for CrossPolytopeHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        crosspolytope_invoke_generic::<u32>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+        )]
+    }
+}
+
+pub struct GeoHash {}
+
+impl VScalar for GeoHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        geo_hash_invoke_generic::<u64>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+        )]
+    }
+}
+
+pub struct GeoHash32 {}
+
+impl VScalar for GeoHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        geo_hash_invoke_generic::<u32>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+        )]
+    }
+}
+
+pub struct CosineHash {}
+
+impl VScalar for CosineHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        cosine_hash_invoke_generic::<u64>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct CosineHash32 {}
+
+impl VScalar for CosineHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        cosine_hash_invoke_generic::<u32>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct MipsItemHash {}
+
+impl VScalar for MipsItemHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        mips_hash_invoke_generic::<u64>(input, output, true)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::Double.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct MipsItemHash32 {}
+
+impl VScalar for MipsItemHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        mips_hash_invoke_generic::<u32>(input, output, true)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::Double.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct MipsQueryHash {}
+
+impl VScalar for MipsQueryHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        mips_hash_invoke_generic::<u64>(input, output, false)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct MipsQueryHash32 {}
+
+impl VScalar for MipsQueryHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        mips_hash_invoke_generic::<u32>(input, output, false)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut vector_types = vec![LogicalTypeHandle::list(&LogicalTypeId::Double.into())];
+        vector_types.extend(sparse_vector_types());
+        vector_types
+            .into_iter()
+            .map(|vector_type| {
+                ScalarFunctionSignature::exact(
+                    vec![
+                        vector_type,
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                        LogicalTypeId::UBigint.into(),
+                    ],
+                    LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The items of a nearest neighbour index under construction, with the
+/// parameters they were hashed with.
+#[derive(Debug, Default)]
+pub struct KnnIndexState {
+    params: Option<(Metric, f64, usize, usize, u64, usize)>,
+    items: Vec<(i64, Vec<f64>, Vec<u64>)>,
+}
+
+impl KnnIndexState {
+    fn set_params(
+        &mut self,
+        params: (Metric, f64, usize, usize, u64, usize),
+    ) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => self.params = Some(params),
+            Some(current) if current.5 != params.5 => {
+                return Err("All input arrays must have the same length".into())
+            }
+            Some(current) if current != params => {
+                return Err(
+                    "Nearest neighbour index parameters must be the same for every row".into(),
+                )
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct KnnIndexAggregate {}
+
+impl VAggregate for KnnIndexAggregate {
+    type State = KnnIndexState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `id` input
+        let input_ids = input.flat_vector(0);
+        let ids = input_ids.as_slice_with_len::<i64>(input.len());
+
+        // Prepare coordinate array input
+        let input_arrays_meta = input.flat_vector(1);
+        let input_arrays_data = input.list_vector(1);
+        let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+        let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+        // Prepare `bucket_width` input
+        let bucket_width = validate_constant_param(
+            input.flat_vector(2).as_slice_with_len::<f64>(input.len()),
+            "bucket_width",
+        )?;
+        if bucket_width <= 0.0 {
+            return Err("bucket_width must be positive".into());
+        }
+
+        // Prepare `band_count`, `band_size` and `seed` inputs
+        let band_count = validate_count_param(input, 3, "band_count")?;
+        let band_size = validate_count_param(input, 4, "band_size")?;
+        let seed = validate_count_param(input, 5, "seed")? as u64;
+        if band_count == 0 {
+            return Err("band_count must be positive".into());
+        }
+
+        // Prepare `metric` input
+        let metric = if input.num_columns() > 6 {
+            let metrics = input
+                .flat_vector(6)
+                .as_slice_with_len::<duckdb_string_t>(input.len())
+                .iter()
+                .map(|ptr| DuckString::new(&mut { *ptr }).as_str().parse())
+                .collect::<Result<Vec<Metric>, _>>()?;
+            validate_constant_param(&metrics, "metric")?
+        } else {
+            Metric::Euclidean
+        };
+
+        // Add each row's vector and band keys to its group's index
+        let mut hashers: Option<(usize, Vec<EuclideanHasher>)> = None;
+        for (row_idx, (&id, meta)) in ids.iter().zip(arrays_meta).enumerate() {
+            if input_ids.row_is_null(row_idx as u64)
+                || input_arrays_meta.row_is_null(row_idx as u64)
+            {
+                continue; // Skip to the next row
+            }
+            let arr_offset = meta.offset as usize;
+            let arr_length = meta.length as usize;
+            if arr_length == 0 {
+                return Err("Vectors must not be empty".into());
+            }
+            let state = &mut *states[row_idx];
+            state.set_params((
+                metric,
+                bucket_width,
+                band_count,
+                band_size,
+                seed,
+                arr_length,
+            ))?;
+
+            if hashers.as_ref().is_none_or(|(dim, _)| *dim != arr_length) {
+                let band_hashers =
+                    band_hashers(bucket_width, band_count, band_size, arr_length, seed);
+                hashers = Some((arr_length, band_hashers));
+            }
+            let (_, band_hashers) = hashers.as_ref().unwrap();
+            let vector = metric.prepare(&arrays[arr_offset..(arr_offset + arr_length)]);
+            let keys = band_hashers
+                .iter()
+                .map(|hasher| hasher.hash(vector.as_slice().into()))
+                .collect();
+            state.items.push((id, vector, keys));
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            target.items.append(&mut source.items);
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output_blobs = output.flat_vector();
+        for (state_idx, state) in states.iter_mut().enumerate() {
+            let Some((metric, bucket_width, band_count, band_size, seed, dim)) = state.params
+            else {
+                output_blobs.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            let items = std::mem::take(&mut state.items);
+            let index = KnnIndex::new(
+                metric,
+                bucket_width,
+                band_count,
+                band_size,
+                seed,
+                dim,
+                items,
+            );
+            validate_blob_len(index.byte_len(), "Nearest neighbour index")?;
+            output_blobs.insert(offset + state_idx, index.to_bytes().as_slice());
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        let parameters = || {
+            vec![
+                LogicalTypeId::Bigint.into(),
+                AggregateType::list(LogicalTypeId::Double.into()),
+                LogicalTypeId::Double.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+            ]
+        };
+        let mut with_metric = parameters();
+        with_metric.push(LogicalTypeId::Varchar.into());
+        vec![
+            AggregateFunctionSignature::exact(parameters(), LogicalTypeId::Blob.into()),
+            AggregateFunctionSignature::exact(with_metric, LogicalTypeId::Blob.into()),
+        ]
+    }
+}
+
+pub struct KnnQuery {}
+
+impl VScalar for KnnQuery {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `index` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare coordinate array input
+        let input_arrays_meta = input.flat_vector(1);
+        let input_arrays_data = input.list_vector(1);
+        let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+        let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+        // Prepare `k` input
+        let k = validate_constant_param(
+            input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+            "k",
+        )?;
+
+        // Prepare `num_probes` input
+        let num_probes = if input.num_columns() > 3 {
+            validate_constant_param(
+                input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+                "num_probes",
+            )?
+        } else {
+            0
+        };
+
+        // Query the index, which is usually the same for every row
+        let mut index: BlobCache<(KnnIndex, Vec<EuclideanHasher>)> = BlobCache::default();
+        let mut row_neighbours: Vec<Option<Vec<(i64, f64)>>> = Vec::with_capacity(input.len());
+        for (row_idx, (blob, meta)) in blobs.iter().zip(arrays_meta).enumerate() {
+            if input_blobs.row_is_null(row_idx as u64)
+                || input_arrays_meta.row_is_null(row_idx as u64)
+            {
+                row_neighbours.push(None);
+                continue; // Skip to the next row
+            }
+            let mut blob = *blob;
+            let (knn_index, hashers) =
+                &*index.get_or_decode(DuckString::new(&mut blob).as_bytes(), |bytes| {
+                    KnnIndex::from_bytes(bytes).map(|knn_index| {
+                        let hashers = knn_index.hashers();
+                        (knn_index, hashers)
+                    })
+                })?;
+
+            let arr_offset = meta.offset as usize;
+            let arr_length = meta.length as usize;
+            if arr_length != knn_index.dim() {
+                return Err(
+                    "Query vectors must have the same length as the indexed vectors".into(),
+                );
+            }
+            let vector = knn_index
+                .metric()
+                .prepare(&arrays[arr_offset..(arr_offset + arr_length)]);
+            row_neighbours.push(Some(knn_index.query(hashers, &vector, k, num_probes)));
+        }
+
+        // Prepare output
+        let mut output_neighbours = output.list_vector();
+        let neighbours_len_sum: usize = row_neighbours.iter().flatten().map(|n| n.len()).sum();
+        let neighbours_vec = output_neighbours.struct_child(neighbours_len_sum);
+        let mut ids_vec = neighbours_vec.child(0, neighbours_len_sum);
+        let ids: &mut [i64] = ids_vec.as_mut_slice_with_len(neighbours_len_sum);
+        let mut distances_vec = neighbours_vec.child(1, neighbours_len_sum);
+        let distances: &mut [f64] = distances_vec.as_mut_slice_with_len(neighbours_len_sum);
+
+        // Write output
+        let mut neighbour_offset = 0;
+        for (row_idx, neighbours) in row_neighbours.iter().enumerate() {
+            let Some(neighbours) = neighbours else {
+                output_neighbours.set_null(row_idx);
+                continue; // Skip to the next row
+            };
+            for (neighbour_idx, &(id, distance)) in neighbours.iter().enumerate() {
+                ids[neighbour_offset + neighbour_idx] = id;
+                distances[neighbour_offset + neighbour_idx] = distance;
+            }
+            output_neighbours.set_entry(row_idx, neighbour_offset, neighbours.len());
+            neighbour_offset += neighbours.len();
+        }
+        output_neighbours.set_len(neighbour_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let neighbours_type = || {
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("id", LogicalTypeId::Bigint.into()),
+                ("distance", LogicalTypeId::Double.into()),
+            ]))
+        };
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                neighbours_type(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                neighbours_type(),
+            ),
+        ]
+    }
+}
+
+/// The sample a learned hashing model is fitted to, with the parameters of the
+/// model.
+#[derive(Debug, Default)]
+pub struct LearnedFitState {
+    params: Option<(usize, usize, u64, usize)>,
+    vectors: Vec<f64>,
+}
+
+impl LearnedFitState {
+    fn set_params(&mut self, params: (usize, usize, u64, usize)) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => self.params = Some(params),
+            Some(current) if current.3 != params.3 => {
+                return Err("All input arrays must have the same length".into())
+            }
+            Some(current) if current != params => {
+                return Err("Learned hashing parameters must be the same for every row".into())
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct LearnedFitAggregate {}
+
+impl VAggregate for LearnedFitAggregate {
+    type State = LearnedFitState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare coordinate array input
+        let input_arrays_meta = input.flat_vector(0);
+        let input_arrays_data = input.list_vector(0);
+        let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+        let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+        // Prepare `band_count`, `band_size` and `seed` inputs
+        let band_count = validate_count_param(input, 1, "band_count")?;
+        let band_size = validate_count_param(input, 2, "band_size")?;
+        let seed = validate_count_param(input, 3, "seed")? as u64;
+        if band_count == 0 || band_size == 0 {
+            return Err("band_count and band_size must be positive".into());
+        }
+
+        // Add each row's vector to its group's sample
+        for (row_idx, meta) in arrays_meta.iter().enumerate() {
+            if input_arrays_meta.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let arr_offset = meta.offset as usize;
+            let arr_length = meta.length as usize;
+            let state = &mut *states[row_idx];
+            state.set_params((band_count, band_size, seed, arr_length))?;
+            state
+                .vectors
+                .extend_from_slice(&arrays[arr_offset..(arr_offset + arr_length)]);
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            target.vectors.append(&mut source.vectors);
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output_blobs = output.flat_vector();
+        for (state_idx, state) in states.iter_mut().enumerate() {
+            let Some((band_count, band_size, seed, dim)) = state.params else {
+                output_blobs.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            let sample =
+                ArrayView2::from_shape((state.vectors.len() / dim.max(1), dim), &state.vectors)?;
+            let hasher = LearnedHasher::fit(
+                sample,
+                band_count,
+                band_size,
+                &mut StdRng::seed_from_u64(seed),
+            )?;
+            output_blobs.insert(offset + state_idx, hasher.to_bytes().as_slice());
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![
+                AggregateType::list(LogicalTypeId::Double.into()),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+            ],
+            LogicalTypeId::Blob.into(),
+        )]
+    }
+}
+
+unsafe fn learned_hash_invoke_generic<T: HashOutput>(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare coordinate array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: &[f64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+    // Prepare `model` input
+    let input_blobs = input.flat_vector(1);
+    let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Hash with the model, which is usually the same for every row
+    let mut model: BlobCache<LearnedHasher> = BlobCache::default();
+    let mut row_hashes: Vec<Option<Vec<u64>>> = Vec::with_capacity(input.len());
+    for (row_idx, (meta, blob)) in arrays_meta.iter().zip(blobs).enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) || input_blobs.row_is_null(row_idx as u64)
+        {
+            row_hashes.push(None);
+            continue; // Skip to the next row
+        }
+        let mut blob = *blob;
+        let hasher = &*model.get_or_decode(
+            DuckString::new(&mut blob).as_bytes(),
+            LearnedHasher::from_bytes,
+        )?;
+
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        if arr_length != hasher.dimension() {
+            return Err(
+                "Vectors must have the same length as the vectors the model was fitted to".into(),
+            );
+        }
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        row_hashes.push(Some(hasher.hash(ArrayView1::from(arr))));
+    }
+
+    // Prepare output
+    let mut output_hashes = output.list_vector();
+    let hashes_len_sum: usize = row_hashes.iter().flatten().map(|h| h.len()).sum();
+    let mut hashes_vec = output_hashes.child(hashes_len_sum);
+    let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
+
+    // Write output
+    let mut hash_offset = 0;
+    for (row_idx, band_hashes) in row_hashes.iter().enumerate() {
+        let Some(band_hashes) = band_hashes else {
+            output_hashes.set_null(row_idx);
+            continue; // Skip to the next row
+        };
+        for (band_idx, &band_hash) in band_hashes.iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(band_hash);
+        }
+        output_hashes.set_entry(row_idx, hash_offset, band_hashes.len());
+        hash_offset += band_hashes.len();
+    }
+    output_hashes.set_len(hash_offset);
+
+    Ok(())
+}
+
+pub struct LearnedHash {}
+
+impl VScalar for LearnedHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        learned_hash_invoke_generic::<u64>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::Blob.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+        )]
+    }
+}
+
+pub struct LearnedHash32 {}
+
+impl VScalar for LearnedHash32 {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        learned_hash_invoke_generic::<u32>(input, output)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Double.into()),
+                LogicalTypeId::Blob.into(),
             ],
             LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
         )]
@@ -0,0 +1,85 @@
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Bernoulli;
+use ndarray_rand::RandomExt;
+use rand::Rng;
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+/// Cross-polytope LSH (Andoni et al., 2015) for angular distance: each hash
+/// function pseudo-randomly rotates the vector and returns the closest vertex of
+/// the cross-polytope `{±e_i}`, i.e. the index and sign of the largest
+/// coordinate.
+///
+/// Rotations are three rounds of random sign flips followed by a fast
+/// Walsh–Hadamard transform, over the input zero-padded to a power of two.
+#[derive(Debug)]
+pub struct CrossPolytopeHasher {
+    signs: Array3<f64>,
+}
+
+impl CrossPolytopeHasher {
+    const ROUNDS: usize = 3;
+
+    pub fn new<R: Rng>(band_width: usize, d: usize, rng: &mut R) -> Self {
+        let padded = d.next_power_of_two();
+        Self {
+            signs: Array3::random_using(
+                (band_width, Self::ROUNDS, padded),
+                Bernoulli::new(0.5).expect("could not initialize bernoulli!"),
+                rng,
+            )
+            .map(|&flip| if flip { -1.0 } else { 1.0 }),
+        }
+    }
+
+    pub fn hash(&self, x: ArrayView1<f64>) -> u64 {
+        let padded = self.signs.dim().2;
+        let vertices: Vec<u64> = self
+            .signs
+            .outer_iter()
+            .map(|rounds| {
+                let mut rotated = Array1::zeros(padded);
+                rotated.slice_mut(s![..x.len()]).assign(&x);
+                for signs in rounds.outer_iter() {
+                    rotated *= &signs;
+                    fast_hadamard_transform(&mut rotated);
+                }
+                closest_vertex(&rotated)
+            })
+            .collect();
+
+        let mut hasher = FxHasher::default();
+
+        vertices.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+/// Index of the largest coordinate in absolute value, doubled and offset by one
+/// if that coordinate is negative.
+fn closest_vertex(x: &Array1<f64>) -> u64 {
+    let mut best_idx = 0;
+    for (idx, value) in x.iter().enumerate() {
+        if value.abs() > x[best_idx].abs() {
+            best_idx = idx;
+        }
+    }
+    2 * best_idx as u64 + (x[best_idx] < 0.0) as u64
+}
+
+/// In-place unnormalized Walsh–Hadamard transform; `x.len()` must be a power of two.
+fn fast_hadamard_transform(x: &mut Array1<f64>) {
+    let n = x.len();
+    let mut half = 1;
+    while half < n {
+        for block in (0..n).step_by(2 * half) {
+            for idx in block..block + half {
+                let (a, b) = (x[idx], x[idx + half]);
+                x[idx] = a + b;
+                x[idx + half] = a - b;
+            }
+        }
+        half *= 2;
+    }
+}
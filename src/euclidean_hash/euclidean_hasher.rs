@@ -3,6 +3,8 @@ use ndarray_rand::rand_distr::{Normal, Uniform};
 use ndarray_rand::RandomExt;
 use rand::Rng;
 use rustc_hash::FxHasher;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 use std::hash::{Hash, Hasher};
 
 #[derive(Debug)]
@@ -26,9 +28,79 @@ impl EuclideanHasher {
     }
 
     pub fn hash(&self, x: ArrayView1<f64>) -> u64 {
-        let numerator = x.dot(&self.a_vectors) + &self.b_vectors;
+        let buckets = self.project(x).map(|x| x.ceil());
+        Self::hash_buckets(&buckets)
+    }
+
+    /// Hashes of the bucket containing `x` followed by up to `num_probes`
+    /// neighbouring buckets, most likely to hold near neighbours first.
+    ///
+    /// Neighbours shift the bucket index of one or more dimensions by ±1 and
+    /// are visited in order of the summed squared distance from the projection
+    /// to the crossed boundaries (query-directed probing, Lv et al., 2007).
+    pub fn probe_hashes(&self, x: ArrayView1<f64>, num_probes: usize) -> Vec<u64> {
+        let projected = self.project(x);
+        let buckets = projected.map(|x| x.ceil());
+
+        // Every single-dimension shift, cheapest first
+        let mut shifts: Vec<(f64, usize, f64)> = Vec::with_capacity(2 * buckets.len());
+        for (dim_idx, (&value, &bucket)) in projected.iter().zip(&buckets).enumerate() {
+            shifts.push(((value - (bucket - 1.0)).powi(2), dim_idx, -1.0));
+            shifts.push(((bucket - value).powi(2), dim_idx, 1.0));
+        }
+        shifts.sort_by(|a, b| a.0.total_cmp(&b.0));
 
-        let rounded = (numerator / self.r).map(|x| x.ceil() as u64);
+        let mut hashes = vec![Self::hash_buckets(&buckets)];
+        let mut heap = BinaryHeap::new();
+        if !shifts.is_empty() {
+            heap.push(Reverse(ProbeSet::new(vec![0], &shifts)));
+        }
+        while hashes.len() <= num_probes {
+            let Some(Reverse(probe_set)) = heap.pop() else {
+                break; // Every combination of shifts has been visited
+            };
+            let last = *probe_set.shift_idxs.last().unwrap();
+            if last + 1 < shifts.len() {
+                // Replace the last shift with the next one
+                let mut replaced = probe_set.shift_idxs.clone();
+                *replaced.last_mut().unwrap() = last + 1;
+                heap.push(Reverse(ProbeSet::new(replaced, &shifts)));
+
+                // Add the next shift
+                let mut extended = probe_set.shift_idxs.clone();
+                extended.push(last + 1);
+                heap.push(Reverse(ProbeSet::new(extended, &shifts)));
+            }
+
+            // Skip sets that shift some dimension both up and down
+            let mut dims: Vec<usize> = probe_set.shift_idxs.iter().map(|&i| shifts[i].1).collect();
+            dims.sort_unstable();
+            if dims.windows(2).any(|pair| pair[0] == pair[1]) {
+                continue;
+            }
+
+            let mut probe = buckets.clone();
+            for &shift_idx in &probe_set.shift_idxs {
+                let (_, dim_idx, delta) = shifts[shift_idx];
+                probe[dim_idx] += delta;
+            }
+            let hash = Self::hash_buckets(&probe);
+            if !hashes.contains(&hash) {
+                hashes.push(hash); // Shifts below bucket 0 saturate onto known buckets
+            }
+        }
+
+        hashes
+    }
+
+    #[inline]
+    fn project(&self, x: ArrayView1<f64>) -> Array1<f64> {
+        (x.dot(&self.a_vectors) + &self.b_vectors) / self.r
+    }
+
+    #[inline]
+    fn hash_buckets(buckets: &Array1<f64>) -> u64 {
+        let rounded = buckets.map(|&x| x as u64);
 
         let mut hasher = FxHasher::default();
 
@@ -37,3 +109,39 @@ impl EuclideanHasher {
         hasher.finish()
     }
 }
+
+/// A set of single-dimension shifts, ordered by total score
+#[derive(Debug)]
+struct ProbeSet {
+    shift_idxs: Vec<usize>,
+    score: f64,
+}
+
+impl ProbeSet {
+    fn new(shift_idxs: Vec<usize>, shifts: &[(f64, usize, f64)]) -> Self {
+        let score = shift_idxs.iter().map(|&i| shifts[i].0).sum();
+        Self { shift_idxs, score }
+    }
+}
+
+impl PartialEq for ProbeSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ProbeSet {}
+
+impl PartialOrd for ProbeSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ProbeSet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| self.shift_idxs.cmp(&other.shift_idxs))
+    }
+}
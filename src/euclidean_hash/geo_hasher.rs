@@ -0,0 +1,102 @@
+use rand::Rng;
+use rustc_hash::FxHasher;
+use std::f64::consts::PI;
+use std::hash::{Hash, Hasher};
+
+/// Mean radius of the Earth in kilometres
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// The point at `lat` and `lon` degrees on a sphere of the Earth's radius, in
+/// kilometres from its centre.
+pub fn to_cartesian(lat: f64, lon: f64) -> [f64; 3] {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [
+        EARTH_RADIUS_KM * lat.cos() * lon.cos(),
+        EARTH_RADIUS_KM * lat.cos() * lon.sin(),
+        EARTH_RADIUS_KM * lat.sin(),
+    ]
+}
+
+/// A uniformly random rotation, from a uniformly random unit quaternion
+/// (Shoemake, 1992).
+fn random_rotation<R: Rng>(rng: &mut R) -> [[f64; 3]; 3] {
+    let (u1, u2, u3): (f64, f64, f64) = (rng.gen(), rng.gen(), rng.gen());
+    let w = (1.0 - u1).sqrt() * (2.0 * PI * u2).sin();
+    let x = (1.0 - u1).sqrt() * (2.0 * PI * u2).cos();
+    let y = u1.sqrt() * (2.0 * PI * u3).sin();
+    let z = u1.sqrt() * (2.0 * PI * u3).cos();
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+/// Hashes points on the Earth by the cells of `band_size` randomly rotated and
+/// shifted grids of cubes they fall in.
+///
+/// The cubes have a diagonal of `radius_km`, so points sharing a hash are never
+/// further apart than `radius_km` in a straight line, which is at most
+/// `2R·asin(radius_km / 2R)` along the surface. Working in 3-D avoids the
+/// distortion of latitude and longitude near the poles and the antimeridian.
+#[derive(Debug)]
+pub struct GeoHasher {
+    rotations: Vec<[[f64; 3]; 3]>,
+    offsets: Vec<[f64; 3]>,
+    side: f64,
+}
+
+impl GeoHasher {
+    pub fn new<R: Rng>(radius_km: f64, band_size: usize, rng: &mut R) -> Self {
+        let side = radius_km / 3f64.sqrt();
+        let rotations = (0..band_size).map(|_| random_rotation(rng)).collect();
+        let offsets = (0..band_size)
+            .map(|_| {
+                [
+                    rng.gen::<f64>() * side,
+                    rng.gen::<f64>() * side,
+                    rng.gen::<f64>() * side,
+                ]
+            })
+            .collect();
+        Self {
+            rotations,
+            offsets,
+            side,
+        }
+    }
+
+    pub fn hash(&self, point: &[f64; 3]) -> u64 {
+        let cells: Vec<[i64; 3]> = self
+            .rotations
+            .iter()
+            .zip(&self.offsets)
+            .map(|(rotation, offset)| {
+                let mut cell = [0; 3];
+                for (axis, (row, shift)) in rotation.iter().zip(offset).enumerate() {
+                    let value: f64 = row.iter().zip(point).map(|(a, x)| a * x).sum();
+                    cell[axis] = ((value + shift) / self.side).floor() as i64;
+                }
+                cell
+            })
+            .collect();
+
+        let mut hasher = FxHasher::default();
+
+        cells.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
@@ -0,0 +1,273 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use ndarray::ArrayView1;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::euclidean_hasher::EuclideanHasher;
+
+/// Distances the nearest neighbours of a query are ranked by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Euclidean,
+    /// One minus the cosine similarity. Vectors are normalized before they are
+    /// hashed, so that euclidean buckets group vectors of similar direction.
+    Cosine,
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "euclidean" => Ok(Self::Euclidean),
+            "cosine" => Ok(Self::Cosine),
+            _ => Err(format!(
+                "Unknown metric '{}', expected 'euclidean' or 'cosine'",
+                name
+            )),
+        }
+    }
+}
+
+impl Metric {
+    /// Maps `vector` into the space its neighbours are hashed and ranked in.
+    pub fn prepare(self, vector: &[f64]) -> Vec<f64> {
+        match self {
+            Self::Euclidean => vector.to_vec(),
+            Self::Cosine => {
+                let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+                if norm == 0.0 {
+                    return vector.to_vec();
+                }
+                vector.iter().map(|x| x / norm).collect()
+            }
+        }
+    }
+
+    fn distance(self, a: &[f64], b: &[f64]) -> f64 {
+        match self {
+            Self::Euclidean => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+            Self::Cosine => 1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>(),
+        }
+    }
+}
+
+/// The euclidean hashers of every band, drawn from `seed` as `lsh_euclidean` does.
+pub fn band_hashers(
+    bucket_width: f64,
+    band_count: usize,
+    band_size: usize,
+    dim: usize,
+    seed: u64,
+) -> Vec<EuclideanHasher> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..band_count)
+        .map(|_| EuclideanHasher::new(bucket_width, band_size, dim, &mut rng))
+        .collect()
+}
+
+/// An index of vectors bucketed by their `lsh_euclidean` band hashes.
+///
+/// Queries gather the vectors sharing a bucket with the query in any band, and
+/// optionally in its most likely neighbouring buckets, then rank these
+/// candidates by their exact distance.
+#[derive(Debug)]
+pub struct KnnIndex {
+    metric: Metric,
+    bucket_width: f64,
+    band_size: usize,
+    seed: u64,
+    dim: usize,
+    ids: Vec<i64>,
+    vectors: Vec<f64>,
+    keys: Vec<u64>,
+    buckets: Vec<FxHashMap<u64, Vec<usize>>>,
+}
+
+impl KnnIndex {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 2 + 3 * 4 + 8 + 8 + 8;
+
+    /// Builds an index from `(id, vector, band_keys)` items, whose vectors have
+    /// been prepared for `metric` and hashed with [`band_hashers`].
+    pub fn new(
+        metric: Metric,
+        bucket_width: f64,
+        band_count: usize,
+        band_size: usize,
+        seed: u64,
+        dim: usize,
+        items: Vec<(i64, Vec<f64>, Vec<u64>)>,
+    ) -> Self {
+        let mut ids = Vec::with_capacity(items.len());
+        let mut vectors = Vec::with_capacity(items.len() * dim);
+        let mut keys = Vec::with_capacity(items.len() * band_count);
+        for (id, vector, band_keys) in items {
+            ids.push(id);
+            vectors.extend(vector);
+            keys.extend(band_keys);
+        }
+        let mut buckets = vec![FxHashMap::<u64, Vec<usize>>::default(); band_count];
+        for (item_idx, band_keys) in keys.chunks(band_count.max(1)).enumerate() {
+            for (band_buckets, &key) in buckets.iter_mut().zip(band_keys) {
+                band_buckets.entry(key).or_default().push(item_idx);
+            }
+        }
+        Self {
+            metric,
+            bucket_width,
+            band_size,
+            seed,
+            dim,
+            ids,
+            vectors,
+            keys,
+            buckets,
+        }
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn hashers(&self) -> Vec<EuclideanHasher> {
+        band_hashers(
+            self.bucket_width,
+            self.buckets.len(),
+            self.band_size,
+            self.dim,
+            self.seed,
+        )
+    }
+
+    /// Up to `k` indexed vectors closest to `vector`, with their distances,
+    /// closest first. Each band also probes `num_probes` neighbouring buckets.
+    pub fn query(
+        &self,
+        hashers: &[EuclideanHasher],
+        vector: &[f64],
+        k: usize,
+        num_probes: usize,
+    ) -> Vec<(i64, f64)> {
+        let mut candidates: FxHashSet<usize> = FxHashSet::default();
+        for (hasher, band_buckets) in hashers.iter().zip(&self.buckets) {
+            for key in hasher.probe_hashes(ArrayView1::from(vector), num_probes) {
+                if let Some(item_idxs) = band_buckets.get(&key) {
+                    candidates.extend(item_idxs);
+                }
+            }
+        }
+
+        let mut neighbours: Vec<(i64, f64)> = candidates
+            .into_iter()
+            .map(|item_idx| {
+                let item = &self.vectors[item_idx * self.dim..(item_idx + 1) * self.dim];
+                (self.ids[item_idx], self.metric.distance(item, vector))
+            })
+            .collect();
+        neighbours.sort_by(|a, b| match a.1.total_cmp(&b.1) {
+            Ordering::Equal => a.0.cmp(&b.0),
+            ordering => ordering,
+        });
+        neighbours.truncate(k);
+        neighbours
+    }
+
+    /// Length of the serialized index.
+    pub fn byte_len(&self) -> usize {
+        Self::HEADER_LEN + 8 * (self.ids.len() + self.vectors.len() + self.keys.len())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_len());
+        bytes.push(Self::VERSION);
+        bytes.push(match self.metric {
+            Metric::Euclidean => 0,
+            Metric::Cosine => 1,
+        });
+        for value in [self.buckets.len(), self.band_size, self.dim] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.bucket_width.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.ids.len() as u64).to_le_bytes());
+        for id in &self.ids {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        for value in &self.vectors {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for key in &self.keys {
+            bytes.extend_from_slice(&key.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid nearest neighbour index".to_string();
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(invalid());
+        }
+        let metric = match bytes[1] {
+            0 => Metric::Euclidean,
+            1 => Metric::Cosine,
+            _ => return Err(invalid()),
+        };
+        let header: Vec<usize> = bytes[2..14]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        let (band_count, band_size, dim) = (header[0], header[1], header[2]);
+        let bucket_width = f64::from_le_bytes(bytes[14..22].try_into().unwrap());
+        let seed = u64::from_le_bytes(bytes[22..30].try_into().unwrap());
+        let len = u64::from_le_bytes(bytes[30..38].try_into().unwrap()) as usize;
+        let expected_len = dim
+            .checked_add(band_count)
+            .and_then(|item_len| item_len.checked_add(1))
+            .and_then(|item_len| item_len.checked_mul(len))
+            .and_then(|words| words.checked_mul(8))
+            .and_then(|words_len| words_len.checked_add(Self::HEADER_LEN));
+        if band_count == 0 || dim == 0 || expected_len != Some(bytes.len()) {
+            return Err(invalid());
+        }
+        let words: Vec<[u8; 8]> = bytes[Self::HEADER_LEN..]
+            .chunks_exact(8)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect();
+        let (id_words, rest) = words.split_at(len);
+        let (vector_words, key_words) = rest.split_at(len * dim);
+        let items = id_words
+            .iter()
+            .zip(vector_words.chunks(dim))
+            .zip(key_words.chunks(band_count))
+            .map(|((&id, vector), band_keys)| {
+                (
+                    i64::from_le_bytes(id),
+                    vector.iter().map(|&x| f64::from_le_bytes(x)).collect(),
+                    band_keys.iter().map(|&x| u64::from_le_bytes(x)).collect(),
+                )
+            })
+            .collect();
+        Ok(Self::new(
+            metric,
+            bucket_width,
+            band_count,
+            band_size,
+            seed,
+            dim,
+            items,
+        ))
+    }
+}
@@ -0,0 +1,200 @@
+use ndarray::prelude::*;
+use ndarray_rand::rand_distr::Normal;
+use ndarray_rand::RandomExt;
+use rand::Rng;
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+/// Data-dependent hashing with PCA followed by iterative quantization (ITQ,
+/// Gong & Lazebnik, 2011): vectors are centred, projected onto their top
+/// principal components and rotated so that the sign of each coordinate loses
+/// as little information as possible. Each coordinate's sign is one bit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearnedHasher {
+    mean: Array1<f64>,
+    projection: Array2<f64>,
+    band_count: usize,
+    band_size: usize,
+}
+
+impl LearnedHasher {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 1 + 3 * 4;
+    const ITERATIONS: usize = 50;
+
+    pub fn fit<R: Rng>(
+        sample: ArrayView2<f64>,
+        band_count: usize,
+        band_size: usize,
+        rng: &mut R,
+    ) -> Result<Self, String> {
+        let (n, d) = sample.dim();
+        let num_bits = band_count * band_size;
+        if num_bits > d {
+            return Err(format!(
+                "band_count * band_size ({}) must not exceed the vector length ({})",
+                num_bits, d
+            ));
+        }
+        if n < 2 {
+            return Err("At least two vectors are needed to fit a model".to_string());
+        }
+
+        // PCA: top eigenvectors of the covariance matrix
+        let mean = sample.mean_axis(Axis(0)).unwrap();
+        let centered = &sample - &mean;
+        let covariance = centered.t().dot(&centered) / (n - 1) as f64;
+        let (eigenvalues, eigenvectors) = symmetric_eigen(covariance);
+        let mut order: Vec<usize> = (0..d).collect();
+        order.sort_by(|&a, &b| eigenvalues[b].total_cmp(&eigenvalues[a]));
+        let components = eigenvectors.select(Axis(1), &order[..num_bits]);
+        let projected = centered.dot(&components);
+
+        // ITQ: alternate between the binary codes and the rotation best aligning
+        // the projections with them (orthogonal Procrustes)
+        let mut rotation = orthonormalize(Array2::random_using(
+            (num_bits, num_bits),
+            Normal::new(0.0, 1.0).expect("could not initialize normal!"),
+            rng,
+        ));
+        for _ in 0..Self::ITERATIONS {
+            let codes = projected
+                .dot(&rotation)
+                .map(|&x| if x >= 0.0 { 1.0 } else { -1.0 });
+            rotation = orthonormalize(projected.t().dot(&codes));
+        }
+
+        Ok(Self {
+            mean,
+            projection: components.dot(&rotation),
+            band_count,
+            band_size,
+        })
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.mean.len()
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.band_count
+    }
+
+    /// One hash per band of `band_size` consecutive bits.
+    pub fn hash(&self, x: ArrayView1<f64>) -> Vec<u64> {
+        let bits: Vec<bool> = (&x - &self.mean)
+            .dot(&self.projection)
+            .iter()
+            .map(|&value| value >= 0.0)
+            .collect();
+        bits.chunks(self.band_size.max(1))
+            .take(self.band_count)
+            .map(|band| {
+                let mut hasher = FxHasher::default();
+
+                band.hash(&mut hasher);
+
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let d = self.dimension();
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + 8 * (d + self.projection.len()));
+        bytes.push(Self::VERSION);
+        for value in [d, self.band_count, self.band_size] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        for value in self.mean.iter().chain(self.projection.iter()) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid learned hashing model".to_string();
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(invalid());
+        }
+        let header: Vec<usize> = bytes[1..Self::HEADER_LEN]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        let (d, band_count, band_size) = (header[0], header[1], header[2]);
+        let num_bits = band_count.checked_mul(band_size).ok_or_else(invalid)?;
+        let expected_len = num_bits
+            .checked_add(1)
+            .and_then(|row_len| row_len.checked_mul(d))
+            .and_then(|values| values.checked_mul(8))
+            .and_then(|values_len| values_len.checked_add(Self::HEADER_LEN));
+        if expected_len != Some(bytes.len()) {
+            return Err(invalid());
+        }
+        let values: Vec<f64> = bytes[Self::HEADER_LEN..]
+            .chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            mean: Array1::from(values[..d].to_vec()),
+            projection: Array2::from_shape_vec((d, num_bits), values[d..].to_vec())
+                .map_err(|_| invalid())?,
+            band_count,
+            band_size,
+        })
+    }
+}
+
+/// Eigen-decomposition of a symmetric matrix by cyclic Jacobi rotations,
+/// returning the eigenvalues and the eigenvectors as columns.
+fn symmetric_eigen(mut a: Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+    let n = a.nrows();
+    let mut v = Array2::eye(n);
+    let total: f64 = a.iter().map(|x| x * x).sum();
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[[i, j]].powi(2))
+            .sum();
+        if off_diagonal <= 1e-24 * total {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[[p, q]].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (2.0 * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * akp - s * akq;
+                    a[[k, q]] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * apk - s * aqk;
+                    a[[q, k]] = s * apk + c * aqk;
+                }
+                for k in 0..n {
+                    let (vkp, vkq) = (v[[k, p]], v[[k, q]]);
+                    v[[k, p]] = c * vkp - s * vkq;
+                    v[[k, q]] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    (a.diag().to_owned(), v)
+}
+
+/// Closest orthogonal matrix to `m`, `m (mᵀm)^(-1/2)`, which is also the
+/// solution of the orthogonal Procrustes problem for `m = Vᵀ B`.
+fn orthonormalize(m: Array2<f64>) -> Array2<f64> {
+    let (eigenvalues, eigenvectors) = symmetric_eigen(m.t().dot(&m));
+    let inv_sqrt = eigenvalues.map(|&value| 1.0 / value.max(1e-12).sqrt());
+    let inverse_root = (&eigenvectors * &inv_sqrt).dot(&eigenvectors.t());
+    m.dot(&inverse_root)
+}
@@ -0,0 +1,39 @@
+use super::sparse_hasher::SparseVector;
+
+/// Index of the coordinate appended by the Simple-LSH transformations. Input
+/// indices are 32-bit, so it never coincides with a coordinate of the vector.
+const EXTRA_INDEX: i64 = 1 << 32;
+
+fn norm(vector: &[(i64, f64)]) -> f64 {
+    vector.iter().map(|(_, x)| x * x).sum::<f64>().sqrt()
+}
+
+/// Simple-LSH (Neyshabur and Srebro, 2015) transformation of an item: the item
+/// is scaled by `max_norm` into the unit ball and lifted onto the unit sphere
+/// with the extra coordinate `sqrt(1 - |x|²)`.
+pub fn item_transform(vector: &[(i64, f64)], max_norm: f64) -> Result<SparseVector, String> {
+    let scaled_norm = norm(vector) / max_norm;
+    if scaled_norm > 1.0 + 1e-9 {
+        return Err("Item vectors must not be longer than max_norm".to_string());
+    }
+    let mut transformed: SparseVector = vector
+        .iter()
+        .map(|&(index, value)| (index, value / max_norm))
+        .collect();
+    transformed.push((EXTRA_INDEX, (1.0 - scaled_norm.powi(2)).max(0.0).sqrt()));
+    Ok(transformed)
+}
+
+/// Simple-LSH transformation of a query: the query is normalized and its extra
+/// coordinate is zero, so its cosine similarity with a transformed item is the
+/// inner product of the two, scaled by `max_norm` and the norm of the query.
+pub fn query_transform(vector: &[(i64, f64)]) -> SparseVector {
+    let query_norm = norm(vector);
+    if query_norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector
+        .iter()
+        .map(|&(index, value)| (index, value / query_norm))
+        .collect()
+}
@@ -0,0 +1,130 @@
+use rustc_hash::FxHasher;
+use std::f64::consts::PI;
+use std::hash::{Hash, Hasher};
+
+use crate::mix;
+
+/// A sparse vector as `(index, value)` pairs
+pub type SparseVector = Vec<(i64, f64)>;
+
+#[inline]
+fn unit_interval(x: u64) -> f64 {
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A `band_size`-column random projection whose entries are derived from the
+/// seed, band, column and coordinate index when they are needed.
+///
+/// Nothing proportional to the dimension is stored, so sparse vectors of any
+/// dimensionality can be projected in time proportional to their non-zeros.
+#[derive(Debug)]
+pub struct SparseProjection {
+    band_key: u64,
+    band_size: usize,
+}
+
+impl SparseProjection {
+    pub fn new(seed: u64, band_idx: usize, band_size: usize) -> Self {
+        Self {
+            band_key: mix(mix(seed) ^ band_idx as u64),
+            band_size,
+        }
+    }
+
+    /// Standard normal entry of `column` at coordinate `index`, drawn with the
+    /// Box-Muller transform.
+    #[inline]
+    fn gaussian(&self, column: usize, index: i64) -> f64 {
+        let bits = mix(mix(self.band_key ^ column as u64) ^ index as u64);
+        let radius = (-2.0 * (1.0 - unit_interval(bits)).ln()).sqrt();
+        radius * (2.0 * PI * unit_interval(mix(bits))).cos()
+    }
+
+    /// Uniform value in `[0, 1)` for `column`, independent of its entries.
+    #[inline]
+    fn uniform(&self, column: usize) -> f64 {
+        unit_interval(mix(mix(!self.band_key ^ column as u64)))
+    }
+
+    /// The projection of the sparse vector `entries`, given as `(index, value)` pairs.
+    pub fn project(&self, entries: &[(i64, f64)]) -> Vec<f64> {
+        (0..self.band_size)
+            .map(|column| {
+                entries
+                    .iter()
+                    .map(|&(index, value)| value * self.gaussian(column, index))
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// `EuclideanHasher` for sparse vectors: the vector is projected onto
+/// `band_size` random directions, offset and cut into buckets of width `r`.
+#[derive(Debug)]
+pub struct SparseEuclideanHasher {
+    projection: SparseProjection,
+    offsets: Vec<f64>,
+    r: f64,
+}
+
+impl SparseEuclideanHasher {
+    pub fn new(r: f64, seed: u64, band_idx: usize, band_size: usize) -> Self {
+        let projection = SparseProjection::new(seed, band_idx, band_size);
+        let offsets = (0..band_size)
+            .map(|column| projection.uniform(column) * r)
+            .collect();
+        Self {
+            projection,
+            offsets,
+            r,
+        }
+    }
+
+    pub fn hash(&self, entries: &[(i64, f64)]) -> u64 {
+        let buckets: Vec<i64> = self
+            .projection
+            .project(entries)
+            .iter()
+            .zip(&self.offsets)
+            .map(|(value, offset)| ((value + offset) / self.r).ceil() as i64)
+            .collect();
+
+        let mut hasher = FxHasher::default();
+
+        buckets.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+/// Random hyperplane LSH (Charikar, 2002) for cosine similarity: each of the
+/// `band_size` bits is the side of a random hyperplane the vector falls on, so
+/// two vectors at angle θ agree on a bit with probability 1 - θ/π.
+#[derive(Debug)]
+pub struct CosineHasher {
+    projection: SparseProjection,
+}
+
+impl CosineHasher {
+    pub fn new(seed: u64, band_idx: usize, band_size: usize) -> Self {
+        Self {
+            projection: SparseProjection::new(seed, band_idx, band_size),
+        }
+    }
+
+    pub fn hash(&self, entries: &[(i64, f64)]) -> u64 {
+        let bits: Vec<bool> = self
+            .projection
+            .project(entries)
+            .iter()
+            .map(|&value| value >= 0.0)
+            .collect();
+
+        let mut hasher = FxHasher::default();
+
+        bits.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
@@ -0,0 +1,304 @@
+use std::error::Error;
+
+use rustc_hash::FxHashMap;
+
+use duckdb::{
+    core::{LogicalTypeId, StructVector},
+    vtab::arrow::WritableVector,
+};
+
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+
+/// Ratio of `numerator` to `denominator`, or `None` if the denominator is zero.
+fn ratio(numerator: u64, denominator: u64) -> Option<f64> {
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+/// Harmonic mean of `precision` and `recall`, or `None` if either is.
+fn f1(precision: Option<f64>, recall: Option<f64>) -> Option<f64> {
+    match (precision, recall) {
+        (Some(p), Some(r)) if p + r > 0.0 => Some(2.0 * p * r / (p + r)),
+        (Some(_), Some(_)) => Some(0.0),
+        _ => None,
+    }
+}
+
+/// Writes `values` to row `row_idx` of the `DOUBLE` children of a struct
+/// vector, from child `first_child` on, with `None` as `NULL`.
+fn write_ratios(
+    output_struct: &StructVector,
+    row_idx: usize,
+    capacity: usize,
+    first_child: usize,
+    values: &[Option<f64>],
+) {
+    for (value_idx, value) in values.iter().enumerate() {
+        let mut child = output_struct.child(first_child + value_idx, capacity);
+        match value {
+            Some(value) => child.as_mut_slice_with_len::<f64>(capacity)[row_idx] = *value,
+            None => child.set_null(row_idx),
+        }
+    }
+}
+
+/// Counts of the pairs seen by a blocking evaluation.
+#[derive(Debug, Default)]
+pub struct BlockingMetricsState {
+    total_pairs: Option<u64>,
+    seen: bool,
+    candidates: u64,
+    matches: u64,
+    true_positives: u64,
+}
+
+impl BlockingMetricsState {
+    fn set_total_pairs(&mut self, total_pairs: Option<u64>) -> Result<(), Box<dyn Error>> {
+        if self.seen && self.total_pairs != total_pairs {
+            return Err("total_pairs must be the same for every row".into());
+        }
+        self.total_pairs = total_pairs;
+        self.seen = true;
+        Ok(())
+    }
+}
+
+pub struct BlockingMetrics {}
+
+impl VAggregate for BlockingMetrics {
+    type State = BlockingMetricsState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `candidate` and `match` inputs
+        let input_candidates = input.flat_vector(0);
+        let candidates = input_candidates.as_slice_with_len::<bool>(input.len());
+        let input_matches = input.flat_vector(1);
+        let matches = input_matches.as_slice_with_len::<bool>(input.len());
+
+        // Prepare `total_pairs` input
+        let total_pairs = if input.num_columns() > 2 {
+            Some(validate_count_param(input, 2, "total_pairs")? as u64)
+        } else {
+            None
+        };
+
+        // Count each pair, treating NULL as false
+        for (row_idx, (&candidate, &is_match)) in candidates.iter().zip(matches).enumerate() {
+            let candidate = candidate && !input_candidates.row_is_null(row_idx as u64);
+            let is_match = is_match && !input_matches.row_is_null(row_idx as u64);
+            let state = &mut *states[row_idx];
+            state.set_total_pairs(total_pairs)?;
+            state.candidates += candidate as u64;
+            state.matches += is_match as u64;
+            state.true_positives += (candidate && is_match) as u64;
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if source.seen {
+            target.set_total_pairs(source.total_pairs)?;
+            target.candidates += source.candidates;
+            target.matches += source.matches;
+            target.true_positives += source.true_positives;
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let capacity = offset + states.len();
+        let output_struct = output.struct_vector();
+        let mut counts_vecs: Vec<_> = (0..3).map(|i| output_struct.child(i, capacity)).collect();
+        for (state_idx, state) in states.iter().enumerate() {
+            let row_idx = offset + state_idx;
+            let counts = [state.candidates, state.matches, state.true_positives];
+            for (counts_vec, count) in counts_vecs.iter_mut().zip(counts) {
+                counts_vec.as_mut_slice_with_len::<i64>(capacity)[row_idx] = count as i64;
+            }
+            let reduction_ratio = state.total_pairs.and_then(|total_pairs| {
+                ratio(total_pairs.saturating_sub(state.candidates), total_pairs)
+            });
+            write_ratios(
+                &output_struct,
+                row_idx,
+                capacity,
+                3,
+                &[
+                    ratio(state.true_positives, state.matches),
+                    ratio(state.true_positives, state.candidates),
+                    reduction_ratio,
+                ],
+            );
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        let metrics_type = || {
+            AggregateType::struct_type(vec![
+                ("candidate_count", LogicalTypeId::Bigint.into()),
+                ("match_count", LogicalTypeId::Bigint.into()),
+                ("true_positive_count", LogicalTypeId::Bigint.into()),
+                ("recall", LogicalTypeId::Double.into()),
+                ("precision", LogicalTypeId::Double.into()),
+                ("reduction_ratio", LogicalTypeId::Double.into()),
+            ])
+        };
+        vec![
+            AggregateFunctionSignature::exact(
+                vec![LogicalTypeId::Boolean.into(), LogicalTypeId::Boolean.into()],
+                metrics_type(),
+            ),
+            AggregateFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Boolean.into(),
+                    LogicalTypeId::Boolean.into(),
+                    LogicalTypeId::Bigint.into(),
+                ],
+                metrics_type(),
+            ),
+        ]
+    }
+}
+
+/// Record counts of every pair of predicted cluster and true entity.
+#[derive(Debug, Default)]
+pub struct ClusterMetricsState {
+    counts: FxHashMap<(i64, i64), u64>,
+}
+
+pub struct ClusterMetrics {}
+
+impl VAggregate for ClusterMetrics {
+    type State = ClusterMetricsState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `cluster_id` and `entity_id` inputs
+        let input_clusters = input.flat_vector(0);
+        let clusters = input_clusters.as_slice_with_len::<i64>(input.len());
+        let input_entities = input.flat_vector(1);
+        let entities = input_entities.as_slice_with_len::<i64>(input.len());
+
+        // Count each record
+        for (row_idx, (&cluster, &entity)) in clusters.iter().zip(entities).enumerate() {
+            if input_clusters.row_is_null(row_idx as u64)
+                || input_entities.row_is_null(row_idx as u64)
+            {
+                continue; // Skip to the next row
+            }
+            let state = &mut *states[row_idx];
+            *state.counts.entry((cluster, entity)).or_default() += 1;
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        for (key, count) in source.counts.drain() {
+            *target.counts.entry(key).or_default() += count;
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let capacity = offset + states.len();
+        let output_struct = output.struct_vector();
+        let mut counts_vecs: Vec<_> = (0..3).map(|i| output_struct.child(i, capacity)).collect();
+        for (state_idx, state) in states.iter().enumerate() {
+            let row_idx = offset + state_idx;
+
+            // Sizes of the predicted clusters and the true entities
+            let mut cluster_sizes: FxHashMap<i64, u64> = FxHashMap::default();
+            let mut entity_sizes: FxHashMap<i64, u64> = FxHashMap::default();
+            for (&(cluster, entity), &count) in &state.counts {
+                *cluster_sizes.entry(cluster).or_default() += count;
+                *entity_sizes.entry(entity).or_default() += count;
+            }
+            let record_count: u64 = cluster_sizes.values().sum();
+            let counts = [
+                record_count,
+                cluster_sizes.len() as u64,
+                entity_sizes.len() as u64,
+            ];
+            for (counts_vec, count) in counts_vecs.iter_mut().zip(counts) {
+                counts_vec.as_mut_slice_with_len::<i64>(capacity)[row_idx] = count as i64;
+            }
+
+            // Pairwise metrics count the record pairs sharing a cluster, an
+            // entity or both, and B-cubed metrics average over records the
+            // share of their cluster in their entity and vice versa
+            let pairs = |n: u64| n * n.saturating_sub(1) / 2;
+            let true_positives: u64 = state.counts.values().map(|&n| pairs(n)).sum();
+            let cluster_pairs: u64 = cluster_sizes.values().map(|&n| pairs(n)).sum();
+            let entity_pairs: u64 = entity_sizes.values().map(|&n| pairs(n)).sum();
+            let precision = ratio(true_positives, cluster_pairs);
+            let recall = ratio(true_positives, entity_pairs);
+            let (mut bcubed_precision, mut bcubed_recall) = (0.0, 0.0);
+            for (&(cluster, entity), &count) in &state.counts {
+                let overlap = (count * count) as f64;
+                bcubed_precision += overlap / cluster_sizes[&cluster] as f64;
+                bcubed_recall += overlap / entity_sizes[&entity] as f64;
+            }
+            let bcubed = |sum: f64| (record_count > 0).then(|| sum / record_count as f64);
+            let (bcubed_precision, bcubed_recall) =
+                (bcubed(bcubed_precision), bcubed(bcubed_recall));
+            write_ratios(
+                &output_struct,
+                row_idx,
+                capacity,
+                3,
+                &[
+                    precision,
+                    recall,
+                    f1(precision, recall),
+                    bcubed_precision,
+                    bcubed_recall,
+                    f1(bcubed_precision, bcubed_recall),
+                ],
+            );
+
+            // The cluster edit distance counts the splits of clusters into
+            // their entities and the merges of the pieces of every entity
+            let splits = state.counts.len() - cluster_sizes.len();
+            let merges = state.counts.len() - entity_sizes.len();
+            output_struct
+                .child(9, capacity)
+                .as_mut_slice_with_len::<i64>(capacity)[row_idx] = (splits + merges) as i64;
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![LogicalTypeId::Bigint.into(), LogicalTypeId::Bigint.into()],
+            AggregateType::struct_type(vec![
+                ("record_count", LogicalTypeId::Bigint.into()),
+                ("cluster_count", LogicalTypeId::Bigint.into()),
+                ("entity_count", LogicalTypeId::Bigint.into()),
+                ("pairwise_precision", LogicalTypeId::Double.into()),
+                ("pairwise_recall", LogicalTypeId::Double.into()),
+                ("pairwise_f1", LogicalTypeId::Double.into()),
+                ("bcubed_precision", LogicalTypeId::Double.into()),
+                ("bcubed_recall", LogicalTypeId::Double.into()),
+                ("bcubed_f1", LogicalTypeId::Double.into()),
+                ("cluster_edit_distance", LogicalTypeId::Bigint.into()),
+            ]),
+        )]
+    }
+}
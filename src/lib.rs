@@ -1,14 +1,51 @@
 use std::error::Error;
+use std::ffi::CString;
 
 use duckdb::ffi;
 use duckdb::{Connection, Result};
-use duckdb_loadable_macros::duckdb_entrypoint_c_api;
 
+pub mod aggregate;
+pub mod bucket;
+pub mod cluster;
+pub mod edit_hash;
 pub mod euclidean_hash;
+pub mod evaluation;
 pub mod minhash;
+pub mod sample;
+pub mod scoring;
+pub mod sketch;
 
-use euclidean_hash::{EuclideanHash, EuclideanHash32};
-use minhash::{JaccardSimilarity, MinHash, MinHash32};
+use aggregate::register_aggregate_function;
+
+use bucket::{BucketSample, StopBuckets};
+use cluster::ConnectedComponents;
+use edit_hash::{EditHash, EditHash32};
+use euclidean_hash::{
+    CosineHash, CosineHash32, CrossPolytopeHash, CrossPolytopeHash32, EuclideanHash,
+    EuclideanHash32, EuclideanProbes, EuclideanProbes32, GeoHash, GeoHash32, KnnIndexAggregate,
+    KnnQuery, LearnedFitAggregate, LearnedHash, LearnedHash32, MipsItemHash, MipsItemHash32,
+    MipsQueryHash, MipsQueryHash32,
+};
+use evaluation::{BlockingMetrics, ClusterMetrics};
+use minhash::{
+    BbitJaccardSimilarity, BbitMinHash, CandidateEstimateAggregate, JaccardSimilarity,
+    LshForestIndex, LshForestQuery, LshIndexKeys, LshIndexMetadata, MinHash, MinHash32,
+    MinHashSignature, MongeElkanSimilarity, ShingleFrequencyAggregate, SignatureJaccardSimilarity,
+    SoftJaccardSimilarity, TfidfCosineSimilarity,
+};
+use sample::StratifiedSample;
+use scoring::{FellegiSunterFit, FellegiSunterParams, FellegiSunterScore};
+use sketch::{
+    BottomKCardinality, BottomKContainment, BottomKJaccard, BottomKSketch, BottomKUnion,
+    HyperMinHashCardinality, HyperMinHashIntersection, HyperMinHashJaccard, HyperMinHashSketch,
+    HyperMinHashUnion,
+};
+
+/// Minimum DuckDB version whose C API the extension is built against
+const MIN_DUCKDB_VERSION: &str = match option_env!("DUCKDB_EXTENSION_MIN_DUCKDB_VERSION") {
+    Some(version) => version,
+    None => "v1.2.0",
+};
 
 trait HashOutput: Copy + 'static {
     fn from_u64(value: u64) -> Self;
@@ -26,6 +63,15 @@ impl HashOutput for u32 {
     }
 }
 
+/// SplitMix64 finalizer, which spreads hashes and keys evenly over 64 bits.
+#[inline]
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
 fn validate_constant_param<T: Copy + PartialEq>(
     slice: &[T],
     param_name: &str,
@@ -37,17 +83,223 @@ fn validate_constant_param<T: Copy + PartialEq>(
     Ok(value)
 }
 
-#[duckdb_entrypoint_c_api()]
-pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>> {
+/// Largest `BLOB` DuckDB can store, as its string lengths are 32-bit.
+const MAX_BLOB_LEN: usize = u32::MAX as usize;
+
+/// Checks that an index of `len` bytes fits in a single `BLOB`.
+fn validate_blob_len(len: usize, index_name: &str) -> Result<(), Box<dyn Error>> {
+    if len > MAX_BLOB_LEN {
+        return Err(format!(
+            "{} of {} bytes exceeds the 4 GiB BLOB limit, build one index per partition of the rows",
+            index_name, len
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The decoded value of the last blob read, so that a model or index passed as
+/// a constant `BLOB` is decoded once rather than for every row.
+///
+/// Blobs are matched by content, except that a blob at the address of the last
+/// one matched is taken to be the same, which only holds within a chunk.
+struct BlobCache<T> {
+    bytes: Vec<u8>,
+    address: Option<(usize, usize)>,
+    value: Option<T>,
+}
+
+impl<T> Default for BlobCache<T> {
+    fn default() -> Self {
+        Self {
+            bytes: Vec::new(),
+            address: None,
+            value: None,
+        }
+    }
+}
+
+impl<T> BlobCache<T> {
+    /// Forgets the address of the last blob, whose memory a later chunk may
+    /// reuse for another one.
+    fn start_chunk(&mut self) {
+        self.address = None;
+    }
+
+    /// Value decoded from `bytes`, decoding them unless they are the last
+    /// blob's.
+    fn get_or_decode<E>(
+        &mut self,
+        bytes: &[u8],
+        decode: impl FnOnce(&[u8]) -> Result<T, E>,
+    ) -> Result<&mut T, E> {
+        let address = (bytes.as_ptr() as usize, bytes.len());
+        let is_cached =
+            self.value.is_some() && (self.address == Some(address) || self.bytes == bytes);
+        if !is_cached {
+            self.value = Some(decode(bytes)?);
+            self.bytes = bytes.to_vec();
+        }
+        self.address = Some(address);
+        Ok(self.value.as_mut().unwrap())
+    }
+}
+
+/// Registers every function of the extension in `db`.
+///
+/// # Safety
+///
+/// `db` must be an open database.
+pub unsafe fn extension_entrypoint(db: ffi::duckdb_database) -> Result<(), Box<dyn Error>> {
+    let con = Connection::open_from_raw(db.cast())?;
     con.register_scalar_function::<MinHash>("lsh_min")
         .expect("Failed to register lsh_min function");
     con.register_scalar_function::<MinHash32>("lsh_min32")
         .expect("Failed to register lsh_min32 function");
     con.register_scalar_function::<JaccardSimilarity>("lsh_jaccard")
         .expect("Failed to register lsh_jaccard function");
+    con.register_scalar_function::<TfidfCosineSimilarity>("lsh_tfidf_cosine")
+        .expect("Failed to register lsh_tfidf_cosine function");
+    con.register_scalar_function::<MongeElkanSimilarity>("lsh_monge_elkan")
+        .expect("Failed to register lsh_monge_elkan function");
+    con.register_scalar_function::<SoftJaccardSimilarity>("lsh_soft_jaccard")
+        .expect("Failed to register lsh_soft_jaccard function");
+    con.register_scalar_function::<MinHashSignature>("lsh_min_signature")
+        .expect("Failed to register lsh_min_signature function");
+    con.register_scalar_function::<SignatureJaccardSimilarity>("lsh_signature_jaccard")
+        .expect("Failed to register lsh_signature_jaccard function");
+    con.register_scalar_function::<BbitMinHash>("lsh_min_bbit")
+        .expect("Failed to register lsh_min_bbit function");
+    con.register_scalar_function::<BbitJaccardSimilarity>("lsh_bbit_jaccard")
+        .expect("Failed to register lsh_bbit_jaccard function");
+    con.register_scalar_function::<LshForestQuery>("lsh_forest_query")
+        .expect("Failed to register lsh_forest_query function");
+    con.register_scalar_function::<LshIndexMetadata>("lsh_index_meta")
+        .expect("Failed to register lsh_index_meta function");
+    con.register_scalar_function::<LshIndexKeys>("lsh_index_keys")
+        .expect("Failed to register lsh_index_keys function");
+    con.register_scalar_function::<EditHash>("lsh_edit")
+        .expect("Failed to register lsh_edit function");
+    con.register_scalar_function::<EditHash32>("lsh_edit32")
+        .expect("Failed to register lsh_edit32 function");
     con.register_scalar_function::<EuclideanHash>("lsh_euclidean")
         .expect("Failed to register lsh_euclidean function");
     con.register_scalar_function::<EuclideanHash32>("lsh_euclidean32")
         .expect("Failed to register lsh_euclidean32 function");
+    con.register_scalar_function::<EuclideanProbes>("lsh_euclidean_probes")
+        .expect("Failed to register lsh_euclidean_probes function");
+    con.register_scalar_function::<EuclideanProbes32>("lsh_euclidean_probes32")
+        .expect("Failed to register lsh_euclidean_probes32 function");
+    con.register_scalar_function::<CosineHash>("lsh_cosine")
+        .expect("Failed to register lsh_cosine function");
+    con.register_scalar_function::<CosineHash32>("lsh_cosine32")
+        .expect("Failed to register lsh_cosine32 function");
+    con.register_scalar_function::<CrossPolytopeHash>("lsh_crosspolytope")
+        .expect("Failed to register lsh_crosspolytope function");
+    con.register_scalar_function::<CrossPolytopeHash32>("lsh_crosspolytope32")
+        .expect("Failed to register lsh_crosspolytope32 function");
+    con.register_scalar_function::<GeoHash>("lsh_geo")
+        .expect("Failed to register lsh_geo function");
+    con.register_scalar_function::<GeoHash32>("lsh_geo32")
+        .expect("Failed to register lsh_geo32 function");
+    con.register_scalar_function::<LearnedHash>("lsh_learned")
+        .expect("Failed to register lsh_learned function");
+    con.register_scalar_function::<LearnedHash32>("lsh_learned32")
+        .expect("Failed to register lsh_learned32 function");
+    con.register_scalar_function::<MipsItemHash>("lsh_mips_item")
+        .expect("Failed to register lsh_mips_item function");
+    con.register_scalar_function::<MipsItemHash32>("lsh_mips_item32")
+        .expect("Failed to register lsh_mips_item32 function");
+    con.register_scalar_function::<MipsQueryHash>("lsh_mips_query")
+        .expect("Failed to register lsh_mips_query function");
+    con.register_scalar_function::<MipsQueryHash32>("lsh_mips_query32")
+        .expect("Failed to register lsh_mips_query32 function");
+    con.register_scalar_function::<KnnQuery>("lsh_knn")
+        .expect("Failed to register lsh_knn function");
+    con.register_scalar_function::<HyperMinHashCardinality>("lsh_hyperminhash_cardinality")
+        .expect("Failed to register lsh_hyperminhash_cardinality function");
+    con.register_scalar_function::<HyperMinHashJaccard>("lsh_hyperminhash_jaccard")
+        .expect("Failed to register lsh_hyperminhash_jaccard function");
+    con.register_scalar_function::<HyperMinHashIntersection>("lsh_hyperminhash_intersection")
+        .expect("Failed to register lsh_hyperminhash_intersection function");
+    con.register_scalar_function::<BottomKSketch>("lsh_bottomk")
+        .expect("Failed to register lsh_bottomk function");
+    con.register_scalar_function::<BottomKCardinality>("lsh_bottomk_cardinality")
+        .expect("Failed to register lsh_bottomk_cardinality function");
+    con.register_scalar_function::<BottomKJaccard>("lsh_bottomk_jaccard")
+        .expect("Failed to register lsh_bottomk_jaccard function");
+    con.register_scalar_function::<BottomKContainment>("lsh_bottomk_containment")
+        .expect("Failed to register lsh_bottomk_containment function");
+    con.register_scalar_function::<BucketSample>("lsh_bucket_sample")
+        .expect("Failed to register lsh_bucket_sample function");
+    con.register_scalar_function::<FellegiSunterScore>("lsh_fellegi_sunter_score")
+        .expect("Failed to register lsh_fellegi_sunter_score function");
+    con.register_scalar_function::<FellegiSunterParams>("lsh_fellegi_sunter_params")
+        .expect("Failed to register lsh_fellegi_sunter_params function");
+
+    // `duckdb` only wraps scalar and table functions, so aggregates are
+    // registered through a raw connection
+    let mut raw_con: ffi::duckdb_connection = std::ptr::null_mut();
+    if ffi::duckdb_connect(db, &mut raw_con) != ffi::DuckDBSuccess {
+        return Err("Failed to connect to the database".into());
+    }
+    let result = register_aggregate_functions(raw_con);
+    ffi::duckdb_disconnect(&mut raw_con);
+    result
+}
+
+unsafe fn register_aggregate_functions(con: ffi::duckdb_connection) -> Result<(), Box<dyn Error>> {
+    register_aggregate_function::<LshForestIndex>(con, "lsh_forest")?;
+    register_aggregate_function::<KnnIndexAggregate>(con, "lsh_knn_index")?;
+    register_aggregate_function::<LearnedFitAggregate>(con, "lsh_learned_fit")?;
+    register_aggregate_function::<HyperMinHashSketch>(con, "lsh_hyperminhash")?;
+    register_aggregate_function::<HyperMinHashUnion>(con, "lsh_hyperminhash_union")?;
+    register_aggregate_function::<BottomKUnion>(con, "lsh_bottomk_union")?;
+    register_aggregate_function::<ConnectedComponents>(con, "lsh_connected_components")?;
+    register_aggregate_function::<BlockingMetrics>(con, "lsh_blocking_metrics")?;
+    register_aggregate_function::<ClusterMetrics>(con, "lsh_cluster_metrics")?;
+    register_aggregate_function::<CandidateEstimateAggregate>(con, "lsh_estimate_candidates")?;
+    register_aggregate_function::<StopBuckets>(con, "lsh_stop_buckets")?;
+    register_aggregate_function::<ShingleFrequencyAggregate>(con, "lsh_shingle_frequencies")?;
+    register_aggregate_function::<FellegiSunterFit>(con, "lsh_fellegi_sunter_fit")?;
+    register_aggregate_function::<StratifiedSample>(con, "lsh_stratified_sample")?;
     Ok(())
 }
+
+/// # Safety
+///
+/// Internal entrypoint for error handling
+pub unsafe fn lsh_init_c_api_internal(
+    info: ffi::duckdb_extension_info,
+    access: *const ffi::duckdb_extension_access,
+) -> Result<bool, Box<dyn Error>> {
+    let have_api_struct = ffi::duckdb_rs_extension_api_init(info, access, MIN_DUCKDB_VERSION)?;
+    if !have_api_struct {
+        // Likely an API version mismatch, which DuckDB reports itself
+        return Ok(false);
+    }
+
+    let db: ffi::duckdb_database = *(*access).get_database.unwrap()(info);
+    extension_entrypoint(db)?;
+
+    Ok(true)
+}
+
+/// # Safety
+///
+/// Entrypoint that will be called by DuckDB
+#[no_mangle]
+pub unsafe extern "C" fn lsh_init_c_api(
+    info: ffi::duckdb_extension_info,
+    access: *const ffi::duckdb_extension_access,
+) -> bool {
+    match lsh_init_c_api_internal(info, access) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            let message = CString::new(e.to_string())
+                .unwrap_or_else(|_| c"Failed to load the lsh extension".to_owned());
+            (*access).set_error.unwrap()(info, message.as_ptr());
+            false
+        }
+    }
+}
@@ -1,26 +1,180 @@
 use std::error::Error;
+use std::sync::{Arc, Mutex, PoisonError};
 
+use nohash_hasher::IntSet;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 
 use duckdb::ffi::{duckdb_list_entry, duckdb_string_t};
 use duckdb::types::DuckString;
 use duckdb::{
-    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
     vscalar::{ScalarFunctionSignature, VScalar},
     vtab::arrow::WritableVector,
     Result,
 };
 
-use super::{validate_constant_param, HashOutput};
+use super::aggregate::{
+    validate_count_param, validate_seed_param, AggregateFunctionSignature, AggregateInput,
+    AggregateType, VAggregate,
+};
+use super::{validate_blob_len, validate_constant_param, BlobCache, HashOutput};
 
+pub mod bbit;
+pub mod candidates;
+pub mod forest;
+pub mod frequency;
+pub mod hybrid;
+pub mod index;
 pub mod minhasher;
+pub mod oph;
+pub mod probminhash;
 pub mod shingleset;
+pub mod signature;
+pub mod superminhash;
 
+use bbit::BbitSignature;
+use candidates::CandidateEstimate;
+use forest::LshForest;
+use frequency::ShingleFrequencies;
+use index::IndexMetadata;
 use minhasher::MinHasher;
 use shingleset::ShingleSet;
+use signature::{signature_similarity, Algorithm, SignatureHasher};
+
+/// Reads the optional trailing `algorithm` parameter, defaulting to classic MinHash.
+unsafe fn validate_algorithm_param(
+    input: &DataChunkHandle,
+    col_idx: usize,
+) -> Result<Algorithm, Box<dyn Error>> {
+    if input.num_columns() <= col_idx {
+        return Ok(Algorithm::MinHash);
+    }
+    let algorithms = input
+        .flat_vector(col_idx)
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().parse())
+        .collect::<Result<Vec<Algorithm>, _>>()?;
+    validate_constant_param(&algorithms, "algorithm")
+}
+
+/// Shingle frequency model of the last `model` parameter read, kept across
+/// chunks so that it is decoded once per query rather than for every chunk.
+#[derive(Default)]
+pub struct ModelCache(Mutex<BlobCache<CachedModel>>);
+
+/// A decoded shingle frequency model, with its stop shingles for the last
+/// `max_document_frequency` asked for.
+struct CachedModel {
+    model: Arc<ShingleFrequencies>,
+    stop_shingles: Option<(f64, Arc<IntSet<u32>>)>,
+}
+
+/// Reads the shingle frequency model at `col_idx`, which must be the same for
+/// every row, and passes it to `f`, or returns `None` if there are no rows.
+unsafe fn with_model_param<R>(
+    input: &DataChunkHandle,
+    col_idx: usize,
+    cache: &ModelCache,
+    f: impl FnOnce(&mut CachedModel) -> Result<R, Box<dyn Error>>,
+) -> Result<Option<R>, Box<dyn Error>> {
+    let input_blobs = input.flat_vector(col_idx);
+    if (0..input.len()).any(|row_idx| input_blobs.row_is_null(row_idx as u64)) {
+        return Err("model must not be NULL".into());
+    }
+    let mut blobs = input_blobs
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .to_vec();
+    let blobs: Vec<&[u8]> = blobs
+        .iter_mut()
+        .map(|blob| DuckString::new(blob).as_bytes())
+        .collect();
+    let Some(&model_bytes) = blobs.first() else {
+        return Ok(None);
+    };
+    if !blobs.iter().all(|bytes| {
+        (bytes.as_ptr() == model_bytes.as_ptr() && bytes.len() == model_bytes.len())
+            || *bytes == model_bytes
+    }) {
+        return Err("model must be a constant value, not vary per row".into());
+    }
+
+    let mut cache = cache.0.lock().unwrap_or_else(PoisonError::into_inner);
+    cache.start_chunk();
+    let cached = cache.get_or_decode(model_bytes, |bytes| {
+        ShingleFrequencies::from_bytes(bytes).map(|model| CachedModel {
+            model: Arc::new(model),
+            stop_shingles: None,
+        })
+    })?;
+    f(cached).map(Some)
+}
+
+/// Reads the shingle frequency model at `col_idx`, which must be the same for
+/// every row, or `None` if there are no rows.
+unsafe fn validate_model_param(
+    input: &DataChunkHandle,
+    col_idx: usize,
+    cache: &ModelCache,
+) -> Result<Option<Arc<ShingleFrequencies>>, Box<dyn Error>> {
+    with_model_param(input, col_idx, cache, |cached| {
+        Ok(Arc::clone(&cached.model))
+    })
+}
+
+/// Reads the optional `model` and `max_document_frequency` parameters at
+/// `col_idx` and `col_idx + 1`, returning the shingles of texts shingled with
+/// `ngram_width` that are too frequent in the model's corpus.
+unsafe fn validate_stop_shingles_param(
+    input: &DataChunkHandle,
+    col_idx: usize,
+    ngram_width: usize,
+    cache: &ModelCache,
+) -> Result<Option<Arc<IntSet<u32>>>, Box<dyn Error>> {
+    if input.num_columns() <= col_idx
+        || input.flat_vector(col_idx).logical_type().id() != LogicalTypeId::Blob
+    {
+        return Ok(None);
+    }
+
+    // Prepare `max_document_frequency` input
+    let max_document_frequency = validate_constant_param(
+        input
+            .flat_vector(col_idx + 1)
+            .as_slice_with_len::<f64>(input.len()),
+        "max_document_frequency",
+    )?;
+
+    // Prepare `model` input
+    with_model_param(input, col_idx, cache, |cached| {
+        if cached.model.ngram_width != ngram_width {
+            return Err(format!(
+                "ngram_width is {} but the model was built with {}",
+                ngram_width, cached.model.ngram_width
+            )
+            .into());
+        }
+        if max_document_frequency <= 0.0 {
+            return Err("max_document_frequency must be positive".into());
+        }
+        match &cached.stop_shingles {
+            Some((cached_frequency, stop_shingles))
+                if *cached_frequency == max_document_frequency =>
+            {
+                Ok(Arc::clone(stop_shingles))
+            }
+            _ => {
+                let stop_shingles = Arc::new(cached.model.stop_shingles(max_document_frequency));
+                cached.stop_shingles = Some((max_document_frequency, Arc::clone(&stop_shingles)));
+                Ok(stop_shingles)
+            }
+        }
+    })
+}
 
 unsafe fn minhash_from_text<T: HashOutput>(
+    cache: &ModelCache,
     input: &mut DataChunkHandle,
     output: &mut dyn WritableVector,
 ) -> Result<(), Box<dyn Error>> {
@@ -55,6 +209,12 @@ unsafe fn minhash_from_text<T: HashOutput>(
         "seed",
     )?;
 
+    // Prepare `model` and `max_document_frequency` inputs
+    let stop_shingles = validate_stop_shingles_param(input, 5, ngram_width, cache)?;
+
+    // Prepare `algorithm` input
+    let algorithm = validate_algorithm_param(input, if stop_shingles.is_some() { 7 } else { 5 })?;
+
     // Prepare output
     let mut output_hashes = output.list_vector();
     let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
@@ -62,17 +222,24 @@ unsafe fn minhash_from_text<T: HashOutput>(
     let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
 
     // Perform hashing
+    let hasher = SignatureHasher::new(
+        algorithm,
+        band_count * band_size,
+        &mut StdRng::seed_from_u64(seed),
+    );
     let mut hash_offset = 0;
     for (row_idx, string) in strings.enumerate() {
         if input_strings.row_is_null(row_idx as u64) {
             output_hashes.set_null(row_idx);
             continue; // Skip to the next row
         }
-        let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
-        let mut rng = StdRng::seed_from_u64(seed);
-        for band_idx in 0..band_count {
-            let hasher = MinHasher::new(band_size, &mut rng);
-            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(&shingle_set));
+        let mut shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+        if let Some(stop_shingles) = &stop_shingles {
+            shingle_set.remove_stop_shingles(stop_shingles);
+        }
+        let band_hashes = hasher.band_hashes(&shingle_set, band_count, band_size);
+        for (band_idx, band_hash) in band_hashes.into_iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(band_hash);
         }
         output_hashes.set_entry(row_idx, hash_offset, band_count);
         hash_offset += band_count;
@@ -115,6 +282,9 @@ unsafe fn minhash_from_shingles<T: HashOutput>(
         "seed",
     )?;
 
+    // Prepare `algorithm` input
+    let algorithm = validate_algorithm_param(input, 4)?;
+
     // Prepare output
     let mut output_hashes = output.list_vector();
     let hashes_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
@@ -122,6 +292,11 @@ unsafe fn minhash_from_shingles<T: HashOutput>(
     let hashes: &mut [T] = hashes_vec.as_mut_slice_with_len(hashes_len_sum);
 
     // Perform hashing
+    let hasher = SignatureHasher::new(
+        algorithm,
+        band_count * band_size,
+        &mut StdRng::seed_from_u64(seed),
+    );
     let mut hash_offset = 0;
     for (row_idx, meta) in arrays_meta.iter().enumerate() {
         if input_arrays_meta.row_is_null(row_idx as u64) {
@@ -135,10 +310,9 @@ unsafe fn minhash_from_shingles<T: HashOutput>(
         let arr_refs: Vec<&str> = arr.iter().map(|s| s.as_str()).collect();
         let shingle_set = ShingleSet::from_shingles(&arr_refs, None);
 
-        let mut rng = StdRng::seed_from_u64(seed);
-        for band_idx in 0..band_count {
-            let hasher = MinHasher::new(band_size, &mut rng);
-            hashes[hash_offset + band_idx] = T::from_u64(hasher.hash(&shingle_set));
+        let band_hashes = hasher.band_hashes(&shingle_set, band_count, band_size);
+        for (band_idx, band_hash) in band_hashes.into_iter().enumerate() {
+            hashes[hash_offset + band_idx] = T::from_u64(band_hash);
         }
 
         output_hashes.set_entry(row_idx, hash_offset, band_count);
@@ -149,18 +323,328 @@ unsafe fn minhash_from_shingles<T: HashOutput>(
     Ok(())
 }
 
+unsafe fn signature_from_text(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare text input
+    let input_strings = input.flat_vector(0);
+    let strings = input_strings
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+    // Prepare `ngram_width` input
+    let ngram_width = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "ngram_width",
+    )?;
+    if ngram_width == 0 {
+        return Err("ngram_width must be positive".into());
+    }
+
+    // Prepare `num_perm` input
+    let num_perm = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "num_perm",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare `algorithm` input
+    let algorithm = validate_algorithm_param(input, 4)?;
+
+    // Prepare output
+    let mut output_signatures = output.list_vector();
+    let values_len_sum: usize = num_perm * input.len(); // Initial estimate assuming no NULLs
+    let mut values_vec = output_signatures.child(values_len_sum);
+    let values: &mut [u64] = values_vec.as_mut_slice_with_len(values_len_sum);
+
+    // Perform hashing
+    let hasher = SignatureHasher::new(algorithm, num_perm, &mut StdRng::seed_from_u64(seed));
+    let mut value_offset = 0;
+    for (row_idx, string) in strings.enumerate() {
+        if input_strings.row_is_null(row_idx as u64) {
+            output_signatures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+        let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+        values[value_offset..(value_offset + num_perm)]
+            .copy_from_slice(&hasher.signature(&shingle_set));
+        output_signatures.set_entry(row_idx, value_offset, num_perm);
+        value_offset += num_perm;
+    }
+    output_signatures.set_len(value_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+unsafe fn signature_from_shingles(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare shingles array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: Vec<String> = arrays_vec
+        .as_slice_with_len::<duckdb_string_t>(input_arrays_data.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Prepare `num_perm` input
+    let num_perm = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "num_perm",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare `algorithm` input
+    let algorithm = validate_algorithm_param(input, 3)?;
+
+    // Prepare output
+    let mut output_signatures = output.list_vector();
+    let values_len_sum: usize = num_perm * input.len(); // Initial estimate assuming no NULLs
+    let mut values_vec = output_signatures.child(values_len_sum);
+    let values: &mut [u64] = values_vec.as_mut_slice_with_len(values_len_sum);
+
+    // Perform hashing
+    let hasher = SignatureHasher::new(algorithm, num_perm, &mut StdRng::seed_from_u64(seed));
+    let mut value_offset = 0;
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            output_signatures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        let arr_refs: Vec<&str> = arr.iter().map(|s| s.as_str()).collect();
+        let shingle_set = ShingleSet::from_shingles(&arr_refs, None);
+
+        values[value_offset..(value_offset + num_perm)]
+            .copy_from_slice(&hasher.signature(&shingle_set));
+        output_signatures.set_entry(row_idx, value_offset, num_perm);
+        value_offset += num_perm;
+    }
+    output_signatures.set_len(value_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+unsafe fn signature_from_weighted_shingles(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare weighted shingles map input
+    let input_maps_meta = input.flat_vector(0);
+    let input_maps_data = input.list_vector(0);
+    let maps_meta = input_maps_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let entries = input_maps_data.struct_child(input_maps_data.len());
+    let keys_vec = entries.child(0, input_maps_data.len());
+    let keys: Vec<u32> = keys_vec
+        .as_slice_with_len::<duckdb_string_t>(input_maps_data.len())
+        .iter()
+        .map(|ptr| ShingleSet::hash_shingle(&DuckString::new(&mut { *ptr }).as_str(), None))
+        .collect();
+    let weights_vec = entries.child(1, input_maps_data.len());
+    let weights: &[f64] = weights_vec.as_slice_with_len(input_maps_data.len());
+
+    // Prepare `num_perm` input
+    let num_perm = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "num_perm",
+    )?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Prepare `algorithm` input
+    let algorithm = validate_algorithm_param(input, 3)?;
+
+    // Prepare output
+    let mut output_signatures = output.list_vector();
+    let values_len_sum: usize = num_perm * input.len(); // Initial estimate assuming no NULLs
+    let mut values_vec = output_signatures.child(values_len_sum);
+    let values: &mut [u64] = values_vec.as_mut_slice_with_len(values_len_sum);
+
+    // Perform hashing
+    let hasher = SignatureHasher::new(algorithm, num_perm, &mut StdRng::seed_from_u64(seed));
+    let mut value_offset = 0;
+    for (row_idx, meta) in maps_meta.iter().enumerate() {
+        if input_maps_meta.row_is_null(row_idx as u64) {
+            output_signatures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let map_offset = meta.offset as usize;
+        let map_length = meta.length as usize;
+        let weighted_shingles: Vec<(u32, f64)> = (map_offset..(map_offset + map_length))
+            .filter(|&entry_idx| !weights_vec.row_is_null(entry_idx as u64))
+            .map(|entry_idx| (keys[entry_idx], weights[entry_idx]))
+            .collect();
+
+        values[value_offset..(value_offset + num_perm)]
+            .copy_from_slice(&hasher.weighted_signature(&weighted_shingles)?);
+        output_signatures.set_entry(row_idx, value_offset, num_perm);
+        value_offset += num_perm;
+    }
+    output_signatures.set_len(value_offset); // Corrects initial estimate if NULLs exist
+
+    Ok(())
+}
+
+fn validate_bbit_param(bits: usize) -> Result<u8, Box<dyn Error>> {
+    if bits == 0 || bits > BbitSignature::MAX_BITS as usize {
+        return Err(format!("bits must be between 1 and {}", BbitSignature::MAX_BITS).into());
+    }
+    Ok(bits as u8)
+}
+
+unsafe fn bbit_minhash_from_text(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare text input
+    let input_strings = input.flat_vector(0);
+    let strings = input_strings
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+    // Prepare `ngram_width` input
+    let ngram_width = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "ngram_width",
+    )?;
+    if ngram_width == 0 {
+        return Err("ngram_width must be positive".into());
+    }
+
+    // Prepare `num_perm` input
+    let num_perm = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "num_perm",
+    )?;
+
+    // Prepare `bits` input
+    let bits = validate_bbit_param(validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+        "bits",
+    )?)?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(4).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Perform hashing
+    let mut output_blobs = output.flat_vector();
+    let hasher = MinHasher::new(num_perm, &mut StdRng::seed_from_u64(seed));
+    for (row_idx, string) in strings.enumerate() {
+        if input_strings.row_is_null(row_idx as u64) {
+            output_blobs.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+        let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+        let signature = BbitSignature::from_signature(
+            &hasher.signature(&shingle_set),
+            bits,
+            shingle_set.shingles.is_empty(),
+        );
+        output_blobs.insert(row_idx, signature.to_bytes().as_slice());
+    }
+
+    Ok(())
+}
+
+unsafe fn bbit_minhash_from_shingles(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare shingles array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: Vec<String> = arrays_vec
+        .as_slice_with_len::<duckdb_string_t>(input_arrays_data.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Prepare `num_perm` input
+    let num_perm = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "num_perm",
+    )?;
+
+    // Prepare `bits` input
+    let bits = validate_bbit_param(validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "bits",
+    )?)?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Perform hashing
+    let mut output_blobs = output.flat_vector();
+    let hasher = MinHasher::new(num_perm, &mut StdRng::seed_from_u64(seed));
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            output_blobs.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        let arr_refs: Vec<&str> = arr.iter().map(|s| s.as_str()).collect();
+        let shingle_set = ShingleSet::from_shingles(&arr_refs, None);
+
+        let signature = BbitSignature::from_signature(
+            &hasher.signature(&shingle_set),
+            bits,
+            shingle_set.shingles.is_empty(),
+        );
+        output_blobs.insert(row_idx, signature.to_bytes().as_slice());
+    }
+
+    Ok(())
+}
+
 pub struct MinHash {}
 
 impl VScalar for MinHash {
-    type State = ();
+    type State = ModelCache;
 
     unsafe fn invoke(
-        _: &Self::State,
+        state: &Self::State,
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
         match input.flat_vector(0).logical_type().id() {
-            LogicalTypeId::Varchar => minhash_from_text::<u64>(input, output),
+            LogicalTypeId::Varchar => minhash_from_text::<u64>(state, input, output),
             LogicalTypeId::List => minhash_from_shingles::<u64>(input, output),
             _ => Err("Unsupported argument type for MinHash".into()),
         }
@@ -187,29 +671,6 @@ impl VScalar for MinHash {
                 ],
                 LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
             ),
-        ]
-    }
-}
-
-pub struct MinHash32 {}
-
-impl VScalar for MinHash32 {
-    type State = ();
-
-    unsafe fn invoke(
-        _: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn Error>> {
-        match input.flat_vector(0).logical_type().id() {
-            LogicalTypeId::Varchar => minhash_from_text::<u32>(input, output),
-            LogicalTypeId::List => minhash_from_shingles::<u32>(input, output),
-            _ => Err("Unsupported argument type for MinHash".into()),
-        }
-    }
-
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![
             ScalarFunctionSignature::exact(
                 vec![
                     LogicalTypeId::Varchar.into(),
@@ -217,15 +678,130 @@ impl VScalar for MinHash32 {
                     LogicalTypeId::UBigint.into(),
                     LogicalTypeId::UBigint.into(),
                     LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
                 ],
-                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
             ),
             ScalarFunctionSignature::exact(
                 vec![
-                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeId::Double.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeId::Double.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+        ]
+    }
+}
+
+pub struct MinHash32 {}
+
+impl VScalar for MinHash32 {
+    type State = ModelCache;
+
+    unsafe fn invoke(
+        state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::Varchar => minhash_from_text::<u32>(state, input, output),
+            LogicalTypeId::List => minhash_from_shingles::<u32>(input, output),
+            _ => Err("Unsupported argument type for MinHash".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
                     LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeId::Double.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeId::Double.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
                     LogicalTypeId::UBigint.into(),
                     LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
                 ],
                 LogicalTypeHandle::list(&LogicalTypeId::UInteger.into()),
             ),
@@ -236,10 +812,10 @@ impl VScalar for MinHash32 {
 pub struct JaccardSimilarity {}
 
 impl VScalar for JaccardSimilarity {
-    type State = ();
+    type State = ModelCache;
 
     unsafe fn invoke(
-        _: &Self::State,
+        state: &Self::State,
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
@@ -263,6 +839,9 @@ impl VScalar for JaccardSimilarity {
             "ngram_width",
         )?;
 
+        // Prepare `model` and `max_document_frequency` inputs
+        let stop_shingles = validate_stop_shingles_param(input, 3, ngram_width, state)?;
+
         // Calculate Jaccard similarity for each pair
         let mut output_measures = output.flat_vector();
         for (row_idx, (s_left, s_right)) in strings_left.zip(strings_right).enumerate() {
@@ -273,8 +852,12 @@ impl VScalar for JaccardSimilarity {
                 continue; // Skip to the next row
             }
 
-            let shingle_set_left = ShingleSet::from_text(&s_left, ngram_width, None);
-            let shingle_set_right = ShingleSet::from_text(&s_right, ngram_width, None);
+            let mut shingle_set_left = ShingleSet::from_text(&s_left, ngram_width, None);
+            let mut shingle_set_right = ShingleSet::from_text(&s_right, ngram_width, None);
+            if let Some(stop_shingles) = &stop_shingles {
+                shingle_set_left.remove_stop_shingles(stop_shingles);
+                shingle_set_right.remove_stop_shingles(stop_shingles);
+            }
 
             let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
             measures[row_idx] = shingle_set_left.jaccard_similarity(&shingle_set_right);
@@ -283,14 +866,1216 @@ impl VScalar for JaccardSimilarity {
         Ok(())
     }
 
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Double.into(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Blob.into(),
+                    LogicalTypeId::Double.into(),
+                ],
+                LogicalTypeId::Double.into(),
+            ),
+        ]
+    }
+}
+
+pub struct TfidfCosineSimilarity {}
+
+impl VScalar for TfidfCosineSimilarity {
+    type State = ModelCache;
+
+    unsafe fn invoke(
+        state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `strings_left` input
+        let input_strings_left = input.flat_vector(0);
+        let strings_left = input_strings_left
+            .as_slice_with_len::<duckdb_string_t>(input.len())
+            .iter()
+            .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+        // Prepare `strings_right` input
+        let input_strings_right = input.flat_vector(1);
+        let strings_right = input_strings_right
+            .as_slice_with_len::<duckdb_string_t>(input.len())
+            .iter()
+            .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+        // Prepare `model` input
+        let Some(model) = validate_model_param(input, 2, state)? else {
+            return Ok(());
+        };
+
+        // Calculate TF-IDF cosine similarity for each pair
+        let mut output_measures = output.flat_vector();
+        for (row_idx, (s_left, s_right)) in strings_left.zip(strings_right).enumerate() {
+            if input_strings_left.row_is_null(row_idx as u64)
+                || input_strings_right.row_is_null(row_idx as u64)
+            {
+                output_measures.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+
+            let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+            measures[row_idx] = model.tfidf_cosine_similarity(&s_left, &s_right);
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Blob.into(),
+            ],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+unsafe fn token_similarity_invoke(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    soft_jaccard: bool,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare `strings_left` input
+    let input_strings_left = input.flat_vector(0);
+    let strings_left = input_strings_left
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+    // Prepare `strings_right` input
+    let input_strings_right = input.flat_vector(1);
+    let strings_right = input_strings_right
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string());
+
+    // Prepare `ngram_width` input
+    let ngram_width = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "ngram_width",
+    )?;
+    if ngram_width == 0 {
+        return Err("ngram_width must be positive".into());
+    }
+
+    // Prepare `threshold` input
+    let threshold = if soft_jaccard {
+        validate_constant_param(
+            input.flat_vector(3).as_slice_with_len::<f64>(input.len()),
+            "threshold",
+        )?
+    } else {
+        0.0
+    };
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("threshold must be between 0 and 1".into());
+    }
+
+    // Calculate token similarity for each pair
+    let mut output_measures = output.flat_vector();
+    for (row_idx, (s_left, s_right)) in strings_left.zip(strings_right).enumerate() {
+        if input_strings_left.row_is_null(row_idx as u64)
+            || input_strings_right.row_is_null(row_idx as u64)
+        {
+            output_measures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+        measures[row_idx] = if soft_jaccard {
+            hybrid::soft_jaccard(&s_left, &s_right, ngram_width, threshold)
+        } else {
+            hybrid::monge_elkan(&s_left, &s_right, ngram_width)
+        };
+    }
+
+    Ok(())
+}
+
+pub struct MongeElkanSimilarity {}
+
+impl VScalar for MongeElkanSimilarity {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        token_similarity_invoke(input, output, false)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+pub struct SoftJaccardSimilarity {}
+
+impl VScalar for SoftJaccardSimilarity {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        token_similarity_invoke(input, output, true)
+    }
+
     fn signatures() -> Vec<ScalarFunctionSignature> {
         vec![ScalarFunctionSignature::exact(
             vec![
                 LogicalTypeId::Varchar.into(),
                 LogicalTypeId::Varchar.into(),
                 LogicalTypeId::UBigint.into(),
+                LogicalTypeId::Double.into(),
             ],
             LogicalTypeId::Double.into(),
         )]
     }
 }
+
+pub struct BbitMinHash {}
+
+impl VScalar for BbitMinHash {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::Varchar => bbit_minhash_from_text(input, output),
+            LogicalTypeId::List => bbit_minhash_from_shingles(input, output),
+            _ => Err("Unsupported argument type for b-bit MinHash".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Blob.into(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Blob.into(),
+            ),
+        ]
+    }
+}
+
+pub struct BbitJaccardSimilarity {}
+
+impl VScalar for BbitJaccardSimilarity {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `blobs_left` input
+        let input_blobs_left = input.flat_vector(0);
+        let blobs_left = input_blobs_left.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `blobs_right` input
+        let input_blobs_right = input.flat_vector(1);
+        let blobs_right = input_blobs_right.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Estimate Jaccard similarity for each pair
+        let mut output_measures = output.flat_vector();
+        for (row_idx, (b_left, b_right)) in blobs_left.iter().zip(blobs_right).enumerate() {
+            if input_blobs_left.row_is_null(row_idx as u64)
+                || input_blobs_right.row_is_null(row_idx as u64)
+            {
+                output_measures.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+
+            let signature_left =
+                BbitSignature::from_bytes(DuckString::new(&mut { *b_left }).as_bytes())?;
+            let signature_right =
+                BbitSignature::from_bytes(DuckString::new(&mut { *b_right }).as_bytes())?;
+
+            let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+            measures[row_idx] = signature_left.jaccard_similarity(&signature_right)?;
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into(), LogicalTypeId::Blob.into()],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+pub struct MinHashSignature {}
+
+impl VScalar for MinHashSignature {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::Varchar => signature_from_text(input, output),
+            LogicalTypeId::List => signature_from_shingles(input, output),
+            LogicalTypeId::Map => signature_from_weighted_shingles(input, output),
+            _ => Err("Unsupported argument type for MinHash signature".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::map(
+                        &LogicalTypeId::Varchar.into(),
+                        &LogicalTypeId::Double.into(),
+                    ),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ),
+        ]
+    }
+}
+
+pub struct SignatureJaccardSimilarity {}
+
+impl VScalar for SignatureJaccardSimilarity {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `signatures_left` input
+        let input_signatures_left = input.flat_vector(0);
+        let signatures_left_data = input.list_vector(0);
+        let signatures_left_meta =
+            input_signatures_left.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let signatures_left_vec = signatures_left_data.child(signatures_left_data.len());
+        let signatures_left: &[u64] =
+            signatures_left_vec.as_slice_with_len(signatures_left_data.len());
+
+        // Prepare `signatures_right` input
+        let input_signatures_right = input.flat_vector(1);
+        let signatures_right_data = input.list_vector(1);
+        let signatures_right_meta =
+            input_signatures_right.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let signatures_right_vec = signatures_right_data.child(signatures_right_data.len());
+        let signatures_right: &[u64] =
+            signatures_right_vec.as_slice_with_len(signatures_right_data.len());
+
+        // Estimate Jaccard similarity for each pair
+        let mut output_measures = output.flat_vector();
+        for (row_idx, (meta_left, meta_right)) in signatures_left_meta
+            .iter()
+            .zip(signatures_right_meta)
+            .enumerate()
+        {
+            if input_signatures_left.row_is_null(row_idx as u64)
+                || input_signatures_right.row_is_null(row_idx as u64)
+            {
+                output_measures.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+
+            let left_offset = meta_left.offset as usize;
+            let right_offset = meta_right.offset as usize;
+            let signature_left =
+                &signatures_left[left_offset..(left_offset + meta_left.length as usize)];
+            let signature_right =
+                &signatures_right[right_offset..(right_offset + meta_right.length as usize)];
+
+            let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+            measures[row_idx] = signature_similarity(signature_left, signature_right)?;
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+                LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+            ],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+/// The items of an LSH Forest under construction, with the parameters they were hashed with.
+#[derive(Debug, Default)]
+pub struct LshForestState {
+    params: Option<(usize, usize, usize, u64)>,
+    items: Vec<(i64, Vec<u64>)>,
+}
+
+impl LshForestState {
+    fn set_params(&mut self, params: (usize, usize, usize, u64)) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => self.params = Some(params),
+            Some(current) if current != params => {
+                return Err("LSH Forest parameters must be the same for every row".into())
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct LshForestIndex {}
+
+impl VAggregate for LshForestIndex {
+    type State = LshForestState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `id` input
+        let input_ids = input.flat_vector(0);
+        let ids = input_ids.as_slice_with_len::<i64>(input.len());
+
+        // Prepare text input
+        let input_strings = input.flat_vector(1);
+        let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `ngram_width`, `num_trees`, `depth` and `seed` inputs
+        let ngram_width = validate_count_param(input, 2, "ngram_width")?;
+        let num_trees = validate_count_param(input, 3, "num_trees")?;
+        let depth = validate_count_param(input, 4, "depth")?;
+        let seed = validate_seed_param(input, 5, "seed")?;
+        if ngram_width == 0 {
+            return Err("ngram_width must be positive".into());
+        }
+        if num_trees == 0 || depth == 0 {
+            return Err("num_trees and depth must be positive".into());
+        }
+
+        // Add each row's signature to its group's forest
+        let hasher = MinHasher::new(num_trees * depth, &mut StdRng::seed_from_u64(seed));
+        for (row_idx, (&id, string)) in ids.iter().zip(strings).enumerate() {
+            if input_ids.row_is_null(row_idx as u64) || input_strings.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let string = DuckString::new(&mut { *string }).as_str().to_string();
+            let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+
+            let state = &mut *states[row_idx];
+            state.set_params((ngram_width, num_trees, depth, seed))?;
+            state.items.push((id, hasher.signature(&shingle_set)));
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            target.items.append(&mut source.items);
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output_blobs = output.flat_vector();
+        for (state_idx, state) in states.iter_mut().enumerate() {
+            let Some((ngram_width, num_trees, depth, seed)) = state.params else {
+                output_blobs.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            let items = std::mem::take(&mut state.items);
+            let forest = LshForest::new(ngram_width, num_trees, depth, seed, items);
+            validate_blob_len(forest.byte_len(), "LSH Forest index")?;
+            output_blobs.insert(offset + state_idx, forest.to_bytes().as_slice());
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        // `seed` may be `UBIGINT`, to reuse the permutations of `lsh_min`
+        [LogicalTypeId::Bigint, LogicalTypeId::UBigint]
+            .into_iter()
+            .map(|seed_type| {
+                AggregateFunctionSignature::exact(
+                    vec![
+                        LogicalTypeId::Bigint.into(),
+                        LogicalTypeId::Varchar.into(),
+                        LogicalTypeId::Bigint.into(),
+                        LogicalTypeId::Bigint.into(),
+                        LogicalTypeId::Bigint.into(),
+                        seed_type.into(),
+                    ],
+                    LogicalTypeId::Blob.into(),
+                )
+            })
+            .collect()
+    }
+}
+
+pub struct LshForestQuery {}
+
+impl VScalar for LshForestQuery {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `index` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare text input
+        let input_strings = input.flat_vector(1);
+        let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `k` input
+        let k = validate_constant_param(
+            input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+            "k",
+        )?;
+
+        // Query the forest, which is usually the same for every row
+        let mut forest: BlobCache<(LshForest, MinHasher)> = BlobCache::default();
+        let mut row_matches: Vec<Option<Vec<(i64, f64)>>> = Vec::with_capacity(input.len());
+        for (row_idx, (blob, string)) in blobs.iter().zip(strings).enumerate() {
+            if input_blobs.row_is_null(row_idx as u64) || input_strings.row_is_null(row_idx as u64)
+            {
+                row_matches.push(None);
+                continue; // Skip to the next row
+            }
+            let mut blob = *blob;
+            let (index, hasher) =
+                &*forest.get_or_decode(DuckString::new(&mut blob).as_bytes(), |bytes| {
+                    LshForest::from_bytes(bytes).map(|index| {
+                        let hasher = MinHasher::new(
+                            index.num_perm(),
+                            &mut StdRng::seed_from_u64(index.seed()),
+                        );
+                        (index, hasher)
+                    })
+                })?;
+
+            let string = DuckString::new(&mut { *string }).as_str().to_string();
+            let shingle_set = ShingleSet::from_text(&string, index.ngram_width(), None);
+            row_matches.push(Some(index.query(&hasher.signature(&shingle_set), k)));
+        }
+
+        // Prepare output
+        let mut output_matches = output.list_vector();
+        let matches_len_sum: usize = row_matches.iter().flatten().map(|m| m.len()).sum();
+        let matches_vec = output_matches.struct_child(matches_len_sum);
+        let mut ids_vec = matches_vec.child(0, matches_len_sum);
+        let ids: &mut [i64] = ids_vec.as_mut_slice_with_len(matches_len_sum);
+        let mut similarities_vec = matches_vec.child(1, matches_len_sum);
+        let similarities: &mut [f64] = similarities_vec.as_mut_slice_with_len(matches_len_sum);
+
+        // Write output
+        let mut match_offset = 0;
+        for (row_idx, matches) in row_matches.iter().enumerate() {
+            let Some(matches) = matches else {
+                output_matches.set_null(row_idx);
+                continue; // Skip to the next row
+            };
+            for (match_idx, &(id, similarity)) in matches.iter().enumerate() {
+                ids[match_offset + match_idx] = id;
+                similarities[match_offset + match_idx] = similarity;
+            }
+            output_matches.set_entry(row_idx, match_offset, matches.len());
+            match_offset += matches.len();
+        }
+        output_matches.set_len(match_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Blob.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::UBigint.into(),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("id", LogicalTypeId::Bigint.into()),
+                ("similarity", LogicalTypeId::Double.into()),
+            ])),
+        )]
+    }
+}
+
+/// Logical type of the rows of an LSH index metadata table.
+fn index_metadata_type() -> LogicalTypeHandle {
+    LogicalTypeHandle::struct_type(&[
+        ("version", LogicalTypeId::UInteger.into()),
+        ("algorithm", LogicalTypeId::Varchar.into()),
+        ("ngram_width", LogicalTypeId::UBigint.into()),
+        ("band_count", LogicalTypeId::UBigint.into()),
+        ("band_size", LogicalTypeId::UBigint.into()),
+        ("seed", LogicalTypeId::UBigint.into()),
+        (
+            "fingerprint",
+            LogicalTypeHandle::list(&LogicalTypeId::UBigint.into()),
+        ),
+    ])
+}
+
+/// Reads the index metadata in column `col_idx`, which must be the same for every row.
+unsafe fn validate_index_metadata_param(
+    input: &DataChunkHandle,
+    col_idx: usize,
+) -> Result<IndexMetadata, Box<dyn Error>> {
+    let len = input.len();
+    let input_meta = input.flat_vector(col_idx);
+    if (0..len).any(|row_idx| input_meta.row_is_null(row_idx as u64)) {
+        return Err("Index metadata must not be NULL".into());
+    }
+    let meta = input.struct_vector(col_idx);
+
+    let version = validate_constant_param(
+        meta.child(0, len).as_slice_with_len::<u32>(len),
+        "Index metadata",
+    )?;
+    let algorithms = meta
+        .child(1, len)
+        .as_slice_with_len::<duckdb_string_t>(len)
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().parse())
+        .collect::<Result<Vec<Algorithm>, _>>()?;
+    let algorithm = validate_constant_param(&algorithms, "Index metadata")?;
+    let mut params = [0; 4];
+    for (param_idx, param) in params.iter_mut().enumerate() {
+        *param = validate_constant_param(
+            meta.child(2 + param_idx, len).as_slice_with_len::<u64>(len),
+            "Index metadata",
+        )?;
+    }
+    let [ngram_width, band_count, band_size, seed] = params;
+
+    let fingerprint_meta = meta.child(6, len);
+    let fingerprint_entries = fingerprint_meta.as_slice_with_len::<duckdb_list_entry>(len);
+    let fingerprint_data = meta.list_vector_child(6);
+    let fingerprint_vec = fingerprint_data.child(fingerprint_data.len());
+    let fingerprint_values: &[u64] = fingerprint_vec.as_slice_with_len(fingerprint_data.len());
+    let row_fingerprint = |entry: &duckdb_list_entry| {
+        &fingerprint_values[entry.offset as usize..(entry.offset + entry.length) as usize]
+    };
+    let fingerprint = row_fingerprint(&fingerprint_entries[0]);
+    if !fingerprint_entries
+        .iter()
+        .all(|entry| row_fingerprint(entry) == fingerprint)
+    {
+        return Err("Index metadata must be a constant value, not vary per row".into());
+    }
+
+    let metadata = IndexMetadata {
+        version,
+        algorithm,
+        ngram_width: ngram_width as usize,
+        band_count: band_count as usize,
+        band_size: band_size as usize,
+        seed,
+        fingerprint: fingerprint.to_vec(),
+    };
+    metadata.validate()?;
+    Ok(metadata)
+}
+
+pub struct LshIndexMetadata {}
+
+impl VScalar for LshIndexMetadata {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `ngram_width` input
+        let ngram_width = validate_constant_param(
+            input.flat_vector(0).as_slice_with_len::<usize>(input.len()),
+            "ngram_width",
+        )?;
+        if ngram_width == 0 {
+            return Err("ngram_width must be positive".into());
+        }
+
+        // Prepare `band_count` input
+        let band_count = validate_constant_param(
+            input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+            "band_count",
+        )?;
+
+        // Prepare `band_size` input
+        let band_size = validate_constant_param(
+            input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+            "band_size",
+        )?;
+
+        // Prepare `seed` input
+        let seed = validate_constant_param(
+            input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+            "seed",
+        )?;
+
+        // Prepare `algorithm` input
+        let algorithm = validate_algorithm_param(input, 4)?;
+
+        let metadata = IndexMetadata::new(algorithm, ngram_width, band_count, band_size, seed);
+
+        // Prepare output
+        let output_meta = output.struct_vector();
+        let mut versions_vec = output_meta.child(0, input.len());
+        let algorithms_vec = output_meta.child(1, input.len());
+        let mut params_vecs: Vec<_> = (2..6)
+            .map(|child_idx| output_meta.child(child_idx, input.len()))
+            .collect();
+        let mut fingerprints_meta = output_meta.list_vector_child(6);
+        let fingerprint_len = metadata.fingerprint.len();
+        let fingerprints_len_sum = fingerprint_len * input.len();
+        let mut fingerprints_vec = fingerprints_meta.child(fingerprints_len_sum);
+        let fingerprints: &mut [u64] = fingerprints_vec.as_mut_slice_with_len(fingerprints_len_sum);
+
+        // Write the same metadata to every row
+        let params = [
+            metadata.ngram_width as u64,
+            metadata.band_count as u64,
+            metadata.band_size as u64,
+            metadata.seed,
+        ];
+        for row_idx in 0..input.len() {
+            versions_vec.as_mut_slice_with_len::<u32>(input.len())[row_idx] = metadata.version;
+            algorithms_vec.insert(row_idx, metadata.algorithm.name());
+            for (params_vec, &param) in params_vecs.iter_mut().zip(&params) {
+                params_vec.as_mut_slice_with_len::<u64>(input.len())[row_idx] = param;
+            }
+            fingerprints[row_idx * fingerprint_len..(row_idx + 1) * fingerprint_len]
+                .copy_from_slice(&metadata.fingerprint);
+            fingerprints_meta.set_entry(row_idx, row_idx * fingerprint_len, fingerprint_len);
+        }
+        fingerprints_meta.set_len(fingerprints_len_sum);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                index_metadata_type(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                index_metadata_type(),
+            ),
+        ]
+    }
+}
+
+pub struct LshIndexKeys {}
+
+impl VScalar for LshIndexKeys {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare text input
+        let input_strings = input.flat_vector(0);
+        let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `meta` input
+        let metadata = validate_index_metadata_param(input, 1)?;
+
+        // Prepare the optional query parameters, which must match the index
+        if input.num_columns() > 2 {
+            let ngram_width = validate_constant_param(
+                input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+                "ngram_width",
+            )?;
+            let band_count = validate_constant_param(
+                input.flat_vector(3).as_slice_with_len::<usize>(input.len()),
+                "band_count",
+            )?;
+            let band_size = validate_constant_param(
+                input.flat_vector(4).as_slice_with_len::<usize>(input.len()),
+                "band_size",
+            )?;
+            let seed = validate_constant_param(
+                input.flat_vector(5).as_slice_with_len::<u64>(input.len()),
+                "seed",
+            )?;
+            metadata.check_params(ngram_width, band_count, band_size, seed)?;
+        }
+
+        // Prepare output
+        let band_count = metadata.band_count;
+        let mut output_keys = output.list_vector();
+        let keys_len_sum: usize = band_count * input.len(); // Initial estimate assuming no NULLs
+        let keys_vec = output_keys.struct_child(keys_len_sum);
+        let mut bands_vec = keys_vec.child(0, keys_len_sum);
+        let bands: &mut [u64] = bands_vec.as_mut_slice_with_len(keys_len_sum);
+        let mut band_keys_vec = keys_vec.child(1, keys_len_sum);
+        let band_keys: &mut [u64] = band_keys_vec.as_mut_slice_with_len(keys_len_sum);
+
+        // Perform hashing
+        let hasher = metadata.hasher();
+        let mut key_offset = 0;
+        for (row_idx, string) in strings.iter().enumerate() {
+            if input_strings.row_is_null(row_idx as u64) {
+                output_keys.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+            let string = DuckString::new(&mut { *string }).as_str().to_string();
+            let shingle_set = ShingleSet::from_text(&string, metadata.ngram_width, None);
+            let band_hashes = hasher.band_hashes(&shingle_set, band_count, metadata.band_size);
+            for (band_idx, band_hash) in band_hashes.into_iter().enumerate() {
+                bands[key_offset + band_idx] = band_idx as u64 + 1;
+                band_keys[key_offset + band_idx] = band_hash;
+            }
+            output_keys.set_entry(row_idx, key_offset, band_count);
+            key_offset += band_count;
+        }
+        output_keys.set_len(key_offset); // Corrects initial estimate if NULLs exist
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let keys_type = || {
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("band", LogicalTypeId::UBigint.into()),
+                ("band_key", LogicalTypeId::UBigint.into()),
+            ]))
+        };
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeId::Varchar.into(), index_metadata_type()],
+                keys_type(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    index_metadata_type(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                keys_type(),
+            ),
+        ]
+    }
+}
+
+pub struct ShingleFrequencyAggregate {}
+
+impl VAggregate for ShingleFrequencyAggregate {
+    type State = Option<ShingleFrequencies>;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare text input
+        let input_strings = input.flat_vector(0);
+        let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `ngram_width` input
+        let ngram_width = validate_count_param(input, 1, "ngram_width")?;
+        if ngram_width == 0 {
+            return Err("ngram_width must be positive".into());
+        }
+
+        // Count each row's shingles in its group's model
+        for (row_idx, string) in strings.iter().enumerate() {
+            if input_strings.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let string = DuckString::new(&mut { *string }).as_str().to_string();
+            let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+            let state = &mut *states[row_idx];
+            state
+                .get_or_insert_with(|| ShingleFrequencies::new(ngram_width))
+                .insert(&shingle_set);
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(source) = source.take() {
+            match target {
+                Some(target) if target.ngram_width != source.ngram_width => {
+                    return Err("ngram_width must be the same for every row".into())
+                }
+                Some(target) => target.merge(source),
+                None => *target = Some(source),
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output_blobs = output.flat_vector();
+        for (state_idx, state) in states.iter().enumerate() {
+            match state {
+                Some(model) => output_blobs.insert(offset + state_idx, model.to_bytes().as_slice()),
+                None => output_blobs.set_null(offset + state_idx),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into(), LogicalTypeId::Bigint.into()],
+            LogicalTypeId::Blob.into(),
+        )]
+    }
+}
+
+/// The band keys of the records a candidate estimate is made for, with the
+/// parameters they were hashed with.
+#[derive(Debug, Default)]
+pub struct CandidateEstimateState {
+    params: Option<(usize, usize, usize, u64)>,
+    keys: Vec<u64>,
+}
+
+impl CandidateEstimateState {
+    fn set_params(&mut self, params: (usize, usize, usize, u64)) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => self.params = Some(params),
+            Some(current) if current != params => {
+                return Err("Candidate estimate parameters must be the same for every row".into())
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct CandidateEstimateAggregate {}
+
+impl VAggregate for CandidateEstimateAggregate {
+    type State = CandidateEstimateState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare text input
+        let input_strings = input.flat_vector(0);
+        let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `ngram_width`, `band_count`, `band_size` and `seed` inputs
+        let ngram_width = validate_count_param(input, 1, "ngram_width")?;
+        if ngram_width == 0 {
+            return Err("ngram_width must be positive".into());
+        }
+        let band_count = validate_count_param(input, 2, "band_count")?;
+        let band_size = validate_count_param(input, 3, "band_size")?;
+        let seed = validate_seed_param(input, 4, "seed")?;
+
+        // Add each row's band keys, as `lsh_min` computes them, to its group
+        let hasher = SignatureHasher::new(
+            Algorithm::MinHash,
+            band_count * band_size,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        for (row_idx, string) in strings.iter().enumerate() {
+            if input_strings.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let string = DuckString::new(&mut { *string }).as_str().to_string();
+            let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+
+            let state = &mut *states[row_idx];
+            state.set_params((ngram_width, band_count, band_size, seed))?;
+            state
+                .keys
+                .extend(hasher.band_hashes(&shingle_set, band_count, band_size));
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            target.keys.append(&mut source.keys);
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let estimates: Vec<Option<CandidateEstimate>> = states
+            .iter_mut()
+            .map(|state| {
+                let (_, band_count, _, _) = state.params?;
+                let keys = std::mem::take(&mut state.keys);
+                Some(CandidateEstimate::new(&keys, band_count))
+            })
+            .collect();
+
+        // Prepare output, after the list entries of earlier finalized states
+        let capacity = offset + states.len();
+        let mut output_struct = output.struct_vector();
+        let mut record_counts_vec = output_struct.child(0, capacity);
+        let mut candidate_pairs_vec = output_struct.child(1, capacity);
+        let mut distinct_pairs_vec = output_struct.child(2, capacity);
+        let mut output_bands = output_struct.list_vector_child(3);
+        let mut output_buckets = output_struct.list_vector_child(4);
+        let bands_offset = output_bands.len();
+        let bands_len_sum = bands_offset
+            + estimates
+                .iter()
+                .flatten()
+                .map(|e| e.bands.len())
+                .sum::<usize>();
+        let buckets_offset = output_buckets.len();
+        let buckets_len_sum = buckets_offset
+            + estimates
+                .iter()
+                .flatten()
+                .map(|e| e.largest_buckets.len())
+                .sum::<usize>();
+        let bands_vec = output_bands.struct_child(bands_len_sum);
+        let mut band_idxs_vec = bands_vec.child(0, bands_len_sum);
+        let mut bucket_counts_vec = bands_vec.child(1, bands_len_sum);
+        let mut max_sizes_vec = bands_vec.child(2, bands_len_sum);
+        let mut band_pairs_vec = bands_vec.child(3, bands_len_sum);
+        let mut output_histograms = bands_vec.list_vector_child(4);
+        let histograms_offset = output_histograms.len();
+        let histograms_len_sum = histograms_offset
+            + estimates
+                .iter()
+                .flatten()
+                .flat_map(|e| &e.bands)
+                .map(|band| band.size_histogram.len())
+                .sum::<usize>();
+        let mut histograms_vec = output_histograms.child(histograms_len_sum);
+        let histograms: &mut [i64] = histograms_vec.as_mut_slice_with_len(histograms_len_sum);
+        let buckets_vec = output_buckets.struct_child(buckets_len_sum);
+        let mut bucket_bands_vec = buckets_vec.child(0, buckets_len_sum);
+        let mut bucket_keys_vec = buckets_vec.child(1, buckets_len_sum);
+        let mut bucket_sizes_vec = buckets_vec.child(2, buckets_len_sum);
+
+        // Write output
+        let (mut band_offset, mut histogram_offset, mut bucket_offset) =
+            (bands_offset, histograms_offset, buckets_offset);
+        for (state_idx, estimate) in estimates.iter().enumerate() {
+            let row_idx = offset + state_idx;
+            let Some(estimate) = estimate else {
+                output_struct.set_null(row_idx);
+                record_counts_vec.set_null(row_idx);
+                candidate_pairs_vec.set_null(row_idx);
+                distinct_pairs_vec.set_null(row_idx);
+                output_bands.set_null(row_idx);
+                output_buckets.set_null(row_idx);
+                continue; // Skip to the next state
+            };
+            record_counts_vec.as_mut_slice_with_len::<i64>(capacity)[row_idx] =
+                estimate.record_count as i64;
+            candidate_pairs_vec.as_mut_slice_with_len::<i64>(capacity)[row_idx] =
+                estimate.candidate_pairs as i64;
+            distinct_pairs_vec.as_mut_slice_with_len::<f64>(capacity)[row_idx] =
+                estimate.distinct_candidate_pairs;
+
+            for (band_idx, band) in estimate.bands.iter().enumerate() {
+                let entry_idx = band_offset + band_idx;
+                band_idxs_vec.as_mut_slice_with_len::<u64>(bands_len_sum)[entry_idx] =
+                    band_idx as u64 + 1;
+                bucket_counts_vec.as_mut_slice_with_len::<i64>(bands_len_sum)[entry_idx] =
+                    band.bucket_count as i64;
+                max_sizes_vec.as_mut_slice_with_len::<i64>(bands_len_sum)[entry_idx] =
+                    band.max_bucket_size as i64;
+                band_pairs_vec.as_mut_slice_with_len::<i64>(bands_len_sum)[entry_idx] =
+                    band.candidate_pairs as i64;
+                for (bin, &count) in band.size_histogram.iter().enumerate() {
+                    histograms[histogram_offset + bin] = count as i64;
+                }
+                output_histograms.set_entry(entry_idx, histogram_offset, band.size_histogram.len());
+                histogram_offset += band.size_histogram.len();
+            }
+            output_bands.set_entry(row_idx, band_offset, estimate.bands.len());
+            band_offset += estimate.bands.len();
+
+            for (bucket_idx, bucket) in estimate.largest_buckets.iter().enumerate() {
+                let entry_idx = bucket_offset + bucket_idx;
+                bucket_bands_vec.as_mut_slice_with_len::<u64>(buckets_len_sum)[entry_idx] =
+                    bucket.band_idx as u64 + 1;
+                bucket_keys_vec.as_mut_slice_with_len::<u64>(buckets_len_sum)[entry_idx] =
+                    bucket.band_key;
+                bucket_sizes_vec.as_mut_slice_with_len::<i64>(buckets_len_sum)[entry_idx] =
+                    bucket.size as i64;
+            }
+            output_buckets.set_entry(row_idx, bucket_offset, estimate.largest_buckets.len());
+            bucket_offset += estimate.largest_buckets.len();
+        }
+        output_histograms.set_len(histogram_offset);
+        output_bands.set_len(band_offset);
+        output_buckets.set_len(bucket_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        // `seed` may be `UBIGINT`, to reuse the permutations of `lsh_min`
+        [LogicalTypeId::Bigint, LogicalTypeId::UBigint]
+            .into_iter()
+            .map(|seed_type| {
+                AggregateFunctionSignature::exact(
+                    vec![
+                        LogicalTypeId::Varchar.into(),
+                        LogicalTypeId::Bigint.into(),
+                        LogicalTypeId::Bigint.into(),
+                        LogicalTypeId::Bigint.into(),
+                        seed_type.into(),
+                    ],
+                    AggregateType::struct_type(vec![
+                        ("record_count", LogicalTypeId::Bigint.into()),
+                        ("candidate_pairs", LogicalTypeId::Bigint.into()),
+                        ("distinct_candidate_pairs", LogicalTypeId::Double.into()),
+                        (
+                            "bands",
+                            AggregateType::list(AggregateType::struct_type(vec![
+                                ("band", LogicalTypeId::UBigint.into()),
+                                ("bucket_count", LogicalTypeId::Bigint.into()),
+                                ("max_bucket_size", LogicalTypeId::Bigint.into()),
+                                ("candidate_pairs", LogicalTypeId::Bigint.into()),
+                                (
+                                    "size_histogram",
+                                    AggregateType::list(LogicalTypeId::Bigint.into()),
+                                ),
+                            ])),
+                        ),
+                        (
+                            "largest_buckets",
+                            AggregateType::list(AggregateType::struct_type(vec![
+                                ("band", LogicalTypeId::UBigint.into()),
+                                ("band_key", LogicalTypeId::UBigint.into()),
+                                ("size", LogicalTypeId::Bigint.into()),
+                            ])),
+                        ),
+                    ]),
+                )
+            })
+            .collect()
+    }
+}
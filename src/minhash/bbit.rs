@@ -0,0 +1,106 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct BbitSignature {
+    bits: u8,
+    is_empty: bool,
+    values: Vec<u64>,
+}
+
+impl BbitSignature {
+    pub const MAX_BITS: u8 = 32;
+
+    const HEADER_LEN: usize = 5;
+    /// Set in the bit width byte when the signature is of an empty set, whose
+    /// minimums are all the same placeholder
+    const EMPTY_FLAG: u8 = 0x80;
+
+    pub fn from_signature(signature: &[u64], bits: u8, is_empty: bool) -> Self {
+        let mask = (1u64 << bits) - 1;
+        Self {
+            bits,
+            is_empty,
+            values: signature.iter().map(|value| value & mask).collect(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let bits = self.bits as usize;
+        let mut bytes = vec![0u8; Self::HEADER_LEN + (self.values.len() * bits).div_ceil(8)];
+        bytes[0] = if self.is_empty {
+            self.bits | Self::EMPTY_FLAG
+        } else {
+            self.bits
+        };
+        bytes[1..Self::HEADER_LEN].copy_from_slice(&(self.values.len() as u32).to_le_bytes());
+
+        let packed = &mut bytes[Self::HEADER_LEN..];
+        for (value_idx, value) in self.values.iter().enumerate() {
+            for bit_idx in 0..bits {
+                if (value >> bit_idx) & 1 == 1 {
+                    let pos = value_idx * bits + bit_idx;
+                    packed[pos / 8] |= 1 << (pos % 8);
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < Self::HEADER_LEN {
+            return Err("b-bit signature is truncated".to_string());
+        }
+        let is_empty = bytes[0] & Self::EMPTY_FLAG != 0;
+        let bits = bytes[0] & !Self::EMPTY_FLAG;
+        if bits == 0 || bits > Self::MAX_BITS {
+            return Err(format!("b-bit signature has invalid bit width {}", bits));
+        }
+        let mut len_bytes = [0u8; 4];
+        len_bytes.copy_from_slice(&bytes[1..Self::HEADER_LEN]);
+        let num_perm = u32::from_le_bytes(len_bytes) as usize;
+
+        let packed = &bytes[Self::HEADER_LEN..];
+        if packed.len() != (num_perm * bits as usize).div_ceil(8) {
+            return Err("b-bit signature length does not match its header".to_string());
+        }
+
+        let values = (0..num_perm)
+            .map(|value_idx| {
+                (0..bits as usize).fold(0u64, |value, bit_idx| {
+                    let pos = value_idx * bits as usize + bit_idx;
+                    value | (((packed[pos / 8] >> (pos % 8)) & 1) as u64) << bit_idx
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            bits,
+            is_empty,
+            values,
+        })
+    }
+
+    /// Estimates the Jaccard similarity of the underlying sets, correcting for the
+    /// probability `2^-b` that two differing minimums agree on their lowest `b` bits
+    /// (Li & König, 2010, assuming sets are small relative to the hash space).
+    pub fn jaccard_similarity(&self, b: &Self) -> Result<f64, String> {
+        if self.bits != b.bits || self.values.len() != b.values.len() {
+            return Err(
+                "b-bit signatures must share the same bit width and permutation count".to_string(),
+            );
+        }
+        // Empty sets share no elements, as in `ShingleSet::jaccard_similarity`
+        if self.values.is_empty() || self.is_empty || b.is_empty {
+            return Ok(0.0);
+        }
+
+        let matches = self
+            .values
+            .iter()
+            .zip(&b.values)
+            .filter(|(left, right)| left == right)
+            .count();
+        let match_rate = matches as f64 / self.values.len() as f64;
+        let collision_rate = 0.5_f64.powi(self.bits as i32);
+
+        Ok(((match_rate - collision_rate) / (1.0 - collision_rate)).clamp(0.0, 1.0))
+    }
+}
@@ -0,0 +1,169 @@
+use std::cmp::Reverse;
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+/// Number of groups of identical records whose distinct candidate pairs are
+/// counted exactly to estimate the distinct candidate pairs of every record
+const SAMPLE_SIZE: usize = 1000;
+
+/// Number of largest buckets reported
+const LARGEST_BUCKETS: usize = 10;
+
+/// Bucket statistics of one band.
+#[derive(Debug)]
+pub struct BandStats {
+    pub bucket_count: u64,
+    pub max_bucket_size: u64,
+    pub candidate_pairs: u64,
+    /// Number of buckets with 1, 2–3, 4–7, ... records
+    pub size_histogram: Vec<u64>,
+}
+
+/// A bucket of a band, with the number of records hashed to it.
+#[derive(Debug)]
+pub struct Bucket {
+    pub band_idx: usize,
+    pub band_key: u64,
+    pub size: u64,
+}
+
+/// The candidate pairs a banded LSH self-join of the records would produce.
+#[derive(Debug)]
+pub struct CandidateEstimate {
+    pub record_count: u64,
+    /// Pairs sharing a bucket, counted once for every band they share
+    pub candidate_pairs: u64,
+    /// Estimated pairs sharing a bucket in at least one band, exact for up to
+    /// `SAMPLE_SIZE` distinct records
+    pub distinct_candidate_pairs: f64,
+    pub bands: Vec<BandStats>,
+    pub largest_buckets: Vec<Bucket>,
+}
+
+fn pairs(size: u64) -> u64 {
+    size * size.saturating_sub(1) / 2
+}
+
+impl CandidateEstimate {
+    /// Estimates the candidates of records given by their `band_count` band
+    /// keys each, one record after another in `keys`.
+    pub fn new(keys: &[u64], band_count: usize) -> Self {
+        let records: Vec<&[u64]> = keys.chunks(band_count.max(1)).collect();
+
+        // Bucket sizes of every band
+        let mut band_buckets = vec![FxHashMap::<u64, u64>::default(); band_count];
+        for record in &records {
+            for (buckets, &key) in band_buckets.iter_mut().zip(record.iter()) {
+                *buckets.entry(key).or_default() += 1;
+            }
+        }
+        let bands: Vec<BandStats> = band_buckets
+            .iter()
+            .map(|buckets| {
+                let mut size_histogram = Vec::new();
+                for &size in buckets.values() {
+                    let bin = size.ilog2() as usize;
+                    if size_histogram.len() <= bin {
+                        size_histogram.resize(bin + 1, 0);
+                    }
+                    size_histogram[bin] += 1;
+                }
+                BandStats {
+                    bucket_count: buckets.len() as u64,
+                    max_bucket_size: buckets.values().copied().max().unwrap_or(0),
+                    candidate_pairs: buckets.values().map(|&size| pairs(size)).sum(),
+                    size_histogram,
+                }
+            })
+            .collect();
+
+        let mut largest_buckets: Vec<Bucket> = band_buckets
+            .iter()
+            .enumerate()
+            .flat_map(|(band_idx, buckets)| {
+                buckets
+                    .iter()
+                    .filter(|(_, &size)| size > 1)
+                    .map(move |(&band_key, &size)| Bucket {
+                        band_idx,
+                        band_key,
+                        size,
+                    })
+            })
+            .collect();
+        largest_buckets.sort_unstable_by_key(|b| (Reverse(b.size), b.band_idx, b.band_key));
+        largest_buckets.truncate(LARGEST_BUCKETS);
+
+        Self {
+            record_count: records.len() as u64,
+            candidate_pairs: bands.iter().map(|band| band.candidate_pairs).sum(),
+            distinct_candidate_pairs: Self::distinct_pairs(&records),
+            bands,
+            largest_buckets,
+        }
+    }
+
+    /// Counts the pairs of identical records exactly, and the distinct
+    /// partners of a sample of the other records, scaled up to every record.
+    /// Records are grouped by their band keys and the sample is drawn among
+    /// the groups, those whose band keys hash lowest, so that every group is
+    /// equally likely to be sampled whatever its size and the order of the
+    /// records. A sampled group counts its partners once per record.
+    fn distinct_pairs(records: &[&[u64]]) -> f64 {
+        // Identical records, with their number of occurrences
+        let mut multiplicities: FxHashMap<&[u64], u64> = FxHashMap::default();
+        for &record in records {
+            *multiplicities.entry(record).or_default() += 1;
+        }
+        let identical_pairs: u64 = multiplicities.values().map(|&count| pairs(count)).sum();
+
+        let mut groups: Vec<(u64, &[u64], u64)> = multiplicities
+            .into_iter()
+            .map(|(record, count)| {
+                let mut hasher = FxHasher::default();
+                record.hash(&mut hasher);
+                (hasher.finish(), record, count)
+            })
+            .collect();
+        groups.sort_unstable();
+        let sample_len = groups.len().min(SAMPLE_SIZE);
+
+        // The sampled groups in each bucket
+        let mut sampled_buckets: FxHashMap<(usize, u64), Vec<usize>> = FxHashMap::default();
+        for (sample_idx, &(_, record, _)) in groups[..sample_len].iter().enumerate() {
+            for (band_idx, &key) in record.iter().enumerate() {
+                sampled_buckets
+                    .entry((band_idx, key))
+                    .or_default()
+                    .push(sample_idx);
+            }
+        }
+
+        // Every other group sharing a bucket with a sampled group pairs each
+        // of its records with each of the sampled group's
+        let mut partners = vec![0u64; sample_len];
+        let mut sharing: Vec<usize> = Vec::new();
+        for (group_idx, &(_, record, count)) in groups.iter().enumerate() {
+            sharing.clear();
+            for (band_idx, &key) in record.iter().enumerate() {
+                if let Some(sample_idxs) = sampled_buckets.get(&(band_idx, key)) {
+                    sharing.extend(sample_idxs);
+                }
+            }
+            sharing.sort_unstable();
+            sharing.dedup();
+            for &sample_idx in &sharing {
+                if sample_idx != group_idx {
+                    partners[sample_idx] += groups[sample_idx].2 * count;
+                }
+            }
+        }
+
+        if sample_len == 0 {
+            return 0.0;
+        }
+        let partner_sum: u64 = partners.iter().sum();
+        identical_pairs as f64 + partner_sum as f64 * groups.len() as f64 / sample_len as f64 / 2.0
+    }
+}
@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+
+use rustc_hash::FxHashSet;
+
+/// LSH Forest (Bawa, Condie & Ganesan, 2005): each tree indexes the items by a
+/// sequence of `depth` MinHash values, and a query descends from the longest
+/// shared prefix to shorter ones until it has gathered enough candidates, so no
+/// similarity threshold has to be chosen up front.
+///
+/// The trees are kept as item orders sorted by their keys, in which the items
+/// sharing a prefix with the query form one contiguous range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LshForest {
+    ngram_width: usize,
+    num_trees: usize,
+    depth: usize,
+    seed: u64,
+    ids: Vec<i64>,
+    signatures: Vec<u64>,
+    trees: Vec<Vec<usize>>,
+}
+
+impl LshForest {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 1 + 3 * 4 + 8 + 8;
+
+    /// Builds a forest from `(id, signature)` items whose signatures hold
+    /// `num_trees * depth` minimum hashes.
+    pub fn new(
+        ngram_width: usize,
+        num_trees: usize,
+        depth: usize,
+        seed: u64,
+        items: Vec<(i64, Vec<u64>)>,
+    ) -> Self {
+        let mut ids = Vec::with_capacity(items.len());
+        let mut signatures = Vec::with_capacity(items.len() * num_trees * depth);
+        for (id, signature) in items {
+            ids.push(id);
+            signatures.extend(signature);
+        }
+        let mut forest = Self {
+            ngram_width,
+            num_trees,
+            depth,
+            seed,
+            ids,
+            signatures,
+            trees: Vec::new(),
+        };
+        forest.trees = (0..num_trees)
+            .map(|tree_idx| {
+                let mut order: Vec<usize> = (0..forest.ids.len()).collect();
+                order.sort_by(|&a, &b| forest.key(a, tree_idx).cmp(forest.key(b, tree_idx)));
+                order
+            })
+            .collect();
+        forest
+    }
+
+    pub fn ngram_width(&self) -> usize {
+        self.ngram_width
+    }
+
+    /// Number of minimum hashes in each signature.
+    pub fn num_perm(&self) -> usize {
+        self.num_trees * self.depth
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Up to `k` items most similar to `signature`, with their estimated Jaccard
+    /// similarity, most similar first.
+    pub fn query(&self, signature: &[u64], k: usize) -> Vec<(i64, f64)> {
+        let mut candidates: FxHashSet<usize> = FxHashSet::default();
+        for prefix_len in (1..=self.depth).rev() {
+            for (tree_idx, order) in self.trees.iter().enumerate() {
+                let offset = tree_idx * self.depth;
+                let prefix = &signature[offset..(offset + prefix_len)];
+                let lower = order.partition_point(|&item_idx| {
+                    &self.key(item_idx, tree_idx)[..prefix_len] < prefix
+                });
+                let upper = order.partition_point(|&item_idx| {
+                    &self.key(item_idx, tree_idx)[..prefix_len] <= prefix
+                });
+                candidates.extend(&order[lower..upper]);
+            }
+            if candidates.len() >= k {
+                break;
+            }
+        }
+
+        let mut matches: Vec<(i64, f64)> = candidates
+            .into_iter()
+            .map(|item_idx| (self.ids[item_idx], self.similarity(item_idx, signature)))
+            .collect();
+        matches.sort_by(|a, b| match b.1.total_cmp(&a.1) {
+            Ordering::Equal => a.0.cmp(&b.0),
+            ordering => ordering,
+        });
+        matches.truncate(k);
+        matches
+    }
+
+    /// Length of the serialized index.
+    pub fn byte_len(&self) -> usize {
+        Self::HEADER_LEN + 8 * (self.ids.len() + self.signatures.len())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_len());
+        bytes.push(Self::VERSION);
+        for value in [self.ngram_width, self.num_trees, self.depth] {
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.ids.len() as u64).to_le_bytes());
+        for id in &self.ids {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        for value in &self.signatures {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid LSH Forest index".to_string();
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(invalid());
+        }
+        let header: Vec<usize> = bytes[1..13]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        let (ngram_width, num_trees, depth) = (header[0], header[1], header[2]);
+        if ngram_width == 0 || num_trees == 0 || depth == 0 {
+            return Err(invalid());
+        }
+        let seed = u64::from_le_bytes(bytes[13..21].try_into().unwrap());
+        let len = u64::from_le_bytes(bytes[21..29].try_into().unwrap()) as usize;
+        let expected_len = num_trees
+            .checked_mul(depth)
+            .and_then(|signature_len| signature_len.checked_add(1))
+            .and_then(|item_len| item_len.checked_mul(len))
+            .and_then(|words| words.checked_mul(8))
+            .and_then(|words_len| words_len.checked_add(Self::HEADER_LEN));
+        if expected_len != Some(bytes.len()) {
+            return Err(invalid());
+        }
+        let values: Vec<u64> = bytes[Self::HEADER_LEN..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        let items = values[..len]
+            .iter()
+            .zip(values[len..].chunks(num_trees * depth))
+            .map(|(&id, signature)| (id as i64, signature.to_vec()))
+            .collect();
+        Ok(Self::new(ngram_width, num_trees, depth, seed, items))
+    }
+
+    #[inline]
+    fn key(&self, item_idx: usize, tree_idx: usize) -> &[u64] {
+        let offset = item_idx * self.num_perm() + tree_idx * self.depth;
+        &self.signatures[offset..(offset + self.depth)]
+    }
+
+    /// Fraction of minimum hashes on which the item and `signature` agree.
+    fn similarity(&self, item_idx: usize, signature: &[u64]) -> f64 {
+        let num_perm = self.num_perm();
+        let item_signature = &self.signatures[item_idx * num_perm..(item_idx + 1) * num_perm];
+        let matches = item_signature
+            .iter()
+            .zip(signature)
+            .filter(|(a, b)| a == b)
+            .count();
+        matches as f64 / num_perm as f64
+    }
+}
@@ -0,0 +1,133 @@
+use nohash_hasher::{IntMap, IntSet};
+use rustc_hash::FxHashMap;
+
+use super::shingleset::ShingleSet;
+
+/// Document frequencies of the shingles of a corpus, counting each shingle
+/// once per document it appears in.
+///
+/// Shingles are stored by their `ShingleSet` hashes, so a model only applies
+/// to texts shingled with the `ngram_width` it was built with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShingleFrequencies {
+    pub ngram_width: usize,
+    pub document_count: u64,
+    frequencies: FxHashMap<u32, u64>,
+}
+
+impl ShingleFrequencies {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 1 + 4 + 8;
+    const ENTRY_LEN: usize = 4 + 8;
+
+    pub fn new(ngram_width: usize) -> Self {
+        Self {
+            ngram_width,
+            ..Self::default()
+        }
+    }
+
+    pub fn insert(&mut self, shingle_set: &ShingleSet) {
+        self.document_count += 1;
+        for &shingle in &shingle_set.shingles {
+            *self.frequencies.entry(shingle).or_default() += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.document_count += other.document_count;
+        for (shingle, frequency) in other.frequencies {
+            *self.frequencies.entry(shingle).or_default() += frequency;
+        }
+    }
+
+    /// Number of documents `shingle` appears in.
+    pub fn frequency(&self, shingle: u32) -> u64 {
+        self.frequencies.get(&shingle).copied().unwrap_or(0)
+    }
+
+    /// Smoothed inverse document frequency of `shingle`, as if one more
+    /// document contained every shingle, so that shingles missing from the
+    /// corpus get the largest weight rather than an infinite one.
+    pub fn idf(&self, shingle: u32) -> f64 {
+        ((1 + self.document_count) as f64 / (1 + self.frequency(shingle)) as f64).ln() + 1.0
+    }
+
+    /// Cosine similarity of the TF-IDF weighted shingle vectors of `a` and `b`,
+    /// or 0 if either has no shingles.
+    pub fn tfidf_cosine_similarity(&self, a: &str, b: &str) -> f64 {
+        let weights = |text: &str| -> IntMap<u32, f64> {
+            ShingleSet::shingle_counts(text, self.ngram_width, None)
+                .into_iter()
+                .map(|(shingle, count)| (shingle, count as f64 * self.idf(shingle)))
+                .collect()
+        };
+        let (weights_a, weights_b) = (weights(a), weights(b));
+        let norm = |weights: &IntMap<u32, f64>| weights.values().map(|w| w * w).sum::<f64>().sqrt();
+        let (norm_a, norm_b) = (norm(&weights_a), norm(&weights_b));
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return 0.0;
+        }
+        let dot: f64 = weights_a
+            .iter()
+            .filter_map(|(shingle, w)| weights_b.get(shingle).map(|v| w * v))
+            .sum();
+        (dot / (norm_a * norm_b)).min(1.0)
+    }
+
+    /// Shingles appearing in more than `max_document_frequency` of the
+    /// documents.
+    pub fn stop_shingles(&self, max_document_frequency: f64) -> IntSet<u32> {
+        let max_count = max_document_frequency * self.document_count as f64;
+        self.frequencies
+            .iter()
+            .filter(|(_, &frequency)| frequency as f64 > max_count)
+            .map(|(&shingle, _)| shingle)
+            .collect()
+    }
+
+    /// Serializes the model with its shingles in hash order, so that the same
+    /// corpus always gives the same bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(u32, u64)> = self.frequencies.iter().map(|(&s, &f)| (s, f)).collect();
+        entries.sort_unstable();
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + Self::ENTRY_LEN * entries.len());
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&(self.ngram_width as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.document_count.to_le_bytes());
+        for (shingle, frequency) in entries {
+            bytes.extend_from_slice(&shingle.to_le_bytes());
+            bytes.extend_from_slice(&frequency.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid shingle frequency model".to_string();
+        if bytes.len() < Self::HEADER_LEN
+            || bytes[0] != Self::VERSION
+            || !(bytes.len() - Self::HEADER_LEN).is_multiple_of(Self::ENTRY_LEN)
+        {
+            return Err(invalid());
+        }
+        let ngram_width = u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize;
+        if ngram_width == 0 {
+            return Err(invalid());
+        }
+        let document_count = u64::from_le_bytes(bytes[5..Self::HEADER_LEN].try_into().unwrap());
+        let frequencies = bytes[Self::HEADER_LEN..]
+            .chunks_exact(Self::ENTRY_LEN)
+            .map(|entry| {
+                (
+                    u32::from_le_bytes(entry[..4].try_into().unwrap()),
+                    u64::from_le_bytes(entry[4..].try_into().unwrap()),
+                )
+            })
+            .collect();
+        Ok(Self {
+            ngram_width,
+            document_count,
+            frequencies,
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+
+use super::shingleset::ShingleSet;
+
+/// Words of `text`, split at every character that is not alphanumeric.
+pub fn tokenize(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+/// Similarity of two tokens at least one of which is shorter than
+/// `ngram_width` and so has no shingles: 1 if it is a prefix of the other, as
+/// an initial is of the word it abbreviates, and 0 otherwise.
+fn short_token_similarity(token_a: &str, token_b: &str) -> f64 {
+    if token_a.starts_with(token_b) || token_b.starts_with(token_a) {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Shingle Jaccard similarity of every pair of tokens of `a` and `b`, row by
+/// row. Tokens shorter than `ngram_width` are compared by prefix instead.
+fn token_similarities(a: &[&str], b: &[&str], ngram_width: usize) -> Vec<Vec<f64>> {
+    let shingle_sets_b: Vec<ShingleSet> = b
+        .iter()
+        .map(|token| ShingleSet::from_text(token, ngram_width, None))
+        .collect();
+    a.iter()
+        .map(|token_a| {
+            let shingle_set_a = ShingleSet::from_text(token_a, ngram_width, None);
+            b.iter()
+                .zip(&shingle_sets_b)
+                .map(|(token_b, shingle_set_b)| {
+                    if token_a.chars().count() < ngram_width
+                        || token_b.chars().count() < ngram_width
+                    {
+                        short_token_similarity(token_a, token_b)
+                    } else {
+                        shingle_set_a.jaccard_similarity(shingle_set_b)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Monge-Elkan similarity: the mean, over the tokens of `a`, of their best
+/// similarity to a token of `b`, or 0 if either has no tokens.
+pub fn monge_elkan(a: &str, b: &str, ngram_width: usize) -> f64 {
+    let (tokens_a, tokens_b) = (tokenize(a), tokenize(b));
+    if tokens_a.is_empty() || tokens_b.is_empty() {
+        return 0.0;
+    }
+    let best_sum: f64 = token_similarities(&tokens_a, &tokens_b, ngram_width)
+        .iter()
+        .map(|row| row.iter().copied().fold(0.0, f64::max))
+        .sum();
+    best_sum / tokens_a.len() as f64
+}
+
+/// Soft (fuzzy) Jaccard similarity: tokens are paired one to one, most similar
+/// first, as long as their similarity reaches `threshold`, and the summed
+/// similarity `s` of the pairs counts as the overlap of the token sets, giving
+/// `s / (|a| + |b| - s)`, or 0 if either has no tokens.
+pub fn soft_jaccard(a: &str, b: &str, ngram_width: usize, threshold: f64) -> f64 {
+    let (tokens_a, tokens_b) = (tokenize(a), tokenize(b));
+    if tokens_a.is_empty() || tokens_b.is_empty() {
+        return 0.0;
+    }
+    let similarities = token_similarities(&tokens_a, &tokens_b, ngram_width);
+    let mut pairs: Vec<(f64, usize, usize)> = similarities
+        .iter()
+        .enumerate()
+        .flat_map(|(idx_a, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &similarity)| similarity > 0.0 && similarity >= threshold)
+                .map(move |(idx_b, &similarity)| (similarity, idx_a, idx_b))
+        })
+        .collect();
+    pairs.sort_by_key(|&(similarity, idx_a, idx_b)| (Reverse(similarity.to_bits()), idx_a, idx_b));
+
+    let (mut used_a, mut used_b) = (vec![false; tokens_a.len()], vec![false; tokens_b.len()]);
+    let mut overlap = 0.0;
+    for (similarity, idx_a, idx_b) in pairs {
+        if !used_a[idx_a] && !used_b[idx_b] {
+            used_a[idx_a] = true;
+            used_b[idx_b] = true;
+            overlap += similarity;
+        }
+    }
+    overlap / ((tokens_a.len() + tokens_b.len()) as f64 - overlap)
+}
@@ -0,0 +1,112 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::shingleset::ShingleSet;
+use super::signature::{Algorithm, SignatureHasher};
+
+/// Text whose band keys fingerprint the hashing of an index.
+const FINGERPRINT_TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+/// The parameters an LSH index table was built with, stored alongside its band
+/// keys so that later batches are hashed exactly like the indexed rows.
+///
+/// Besides the user-facing parameters, the metadata records the format version
+/// and the band keys of a fixed probe text, hashed with the index's algorithm,
+/// so that an index built by an incompatible release of the extension is
+/// rejected rather than silently producing keys that never collide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexMetadata {
+    pub version: u32,
+    pub algorithm: Algorithm,
+    pub ngram_width: usize,
+    pub band_count: usize,
+    pub band_size: usize,
+    pub seed: u64,
+    pub fingerprint: Vec<u64>,
+}
+
+impl IndexMetadata {
+    pub const VERSION: u32 = 2;
+
+    pub fn new(
+        algorithm: Algorithm,
+        ngram_width: usize,
+        band_count: usize,
+        band_size: usize,
+        seed: u64,
+    ) -> Self {
+        let mut metadata = Self {
+            version: Self::VERSION,
+            algorithm,
+            ngram_width,
+            band_count,
+            band_size,
+            seed,
+            fingerprint: Vec::new(),
+        };
+        metadata.fingerprint = metadata.compute_fingerprint();
+        metadata
+    }
+
+    /// Checks that this release hashes rows the same way as the one that
+    /// built the index.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != Self::VERSION {
+            return Err(format!(
+                "LSH index version {} is not supported, expected version {}",
+                self.version,
+                Self::VERSION
+            ));
+        }
+        if self.ngram_width == 0 {
+            return Err("LSH index ngram_width must be positive".to_string());
+        }
+        if self.fingerprint != self.compute_fingerprint() {
+            return Err(
+                "LSH index fingerprint does not match how this release hashes rows, rebuild the index"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that query parameters match the ones the index was built with.
+    pub fn check_params(
+        &self,
+        ngram_width: usize,
+        band_count: usize,
+        band_size: usize,
+        seed: u64,
+    ) -> Result<(), String> {
+        let params = [
+            ("ngram_width", self.ngram_width as u64, ngram_width as u64),
+            ("band_count", self.band_count as u64, band_count as u64),
+            ("band_size", self.band_size as u64, band_size as u64),
+            ("seed", self.seed, seed),
+        ];
+        for (name, stored, given) in params {
+            if stored != given {
+                return Err(format!(
+                    "{} is {} but the index was built with {}",
+                    name, given, stored
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The signature hasher rows of the index are hashed with.
+    pub fn hasher(&self) -> SignatureHasher {
+        SignatureHasher::new(
+            self.algorithm,
+            self.band_count * self.band_size,
+            &mut StdRng::seed_from_u64(self.seed),
+        )
+    }
+
+    fn compute_fingerprint(&self) -> Vec<u64> {
+        let shingle_set = ShingleSet::from_text(FINGERPRINT_TEXT, self.ngram_width, None);
+        self.hasher()
+            .band_hashes(&shingle_set, self.band_count, self.band_size)
+    }
+}
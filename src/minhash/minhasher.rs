@@ -19,26 +19,35 @@ impl MinHasher {
         Self { seeds }
     }
 
-    pub fn hash(&self, shingle_set: &ShingleSet) -> u64 {
-        let mini_hashes = self.seeds.iter().map(|seed| {
-            let mut min_hash_seen = u64::MAX;
-            for item in &shingle_set.shingles {
-                let mut hasher = FxHasher::default();
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+
+    pub fn signature(&self, shingle_set: &ShingleSet) -> Vec<u64> {
+        self.seeds
+            .iter()
+            .map(|seed| {
+                let mut min_hash_seen = u64::MAX;
+                for item in &shingle_set.shingles {
+                    let mut hasher = FxHasher::default();
 
-                seed.hash(&mut hasher);
-                item.hash(&mut hasher);
+                    seed.hash(&mut hasher);
+                    item.hash(&mut hasher);
 
-                let result: u64 = hasher.finish();
+                    let result: u64 = hasher.finish();
 
-                if result < min_hash_seen {
-                    min_hash_seen = result;
+                    if result < min_hash_seen {
+                        min_hash_seen = result;
+                    }
                 }
-            }
-            min_hash_seen
-        });
+                min_hash_seen
+            })
+            .collect()
+    }
 
+    pub fn hash(&self, shingle_set: &ShingleSet) -> u64 {
         let mut hasher = FxHasher::default();
-        for mini_hash in mini_hashes {
+        for mini_hash in self.signature(shingle_set) {
             mini_hash.hash(&mut hasher);
         }
         hasher.finish()
@@ -0,0 +1,80 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+use rand::Rng;
+
+use super::shingleset::ShingleSet;
+
+/// One-permutation hashing (Li et al., 2012) with optimal densification
+/// (Shrivastava, 2017): every shingle is hashed once and assigned to one of
+/// `num_bins` bins, and empty bins borrow the minimum of a pseudo-randomly
+/// chosen non-empty bin.
+#[derive(Debug)]
+pub struct OnePermutationHasher {
+    seed: u64,
+    num_bins: usize,
+}
+
+impl OnePermutationHasher {
+    pub fn new<R: Rng>(num_bins: usize, rand_state: &mut R) -> Self {
+        Self {
+            seed: rand_state.gen(),
+            num_bins,
+        }
+    }
+
+    pub fn signature(&self, shingle_set: &ShingleSet) -> Vec<u64> {
+        let mut bins = vec![u64::MAX; self.num_bins];
+        let mut filled = vec![false; self.num_bins];
+        if self.num_bins == 0 {
+            return bins;
+        }
+
+        for item in &shingle_set.shingles {
+            let mut hasher = FxHasher::default();
+
+            self.seed.hash(&mut hasher);
+            item.hash(&mut hasher);
+
+            let result: u64 = hasher.finish();
+            let bin_idx = self.bin_index(result);
+
+            filled[bin_idx] = true;
+            if result < bins[bin_idx] {
+                bins[bin_idx] = result;
+            }
+        }
+
+        if filled.iter().all(|&is_filled| !is_filled) {
+            return bins; // Nothing to borrow from
+        }
+
+        for bin_idx in 0..self.num_bins {
+            if filled[bin_idx] {
+                continue;
+            }
+            let mut attempt: u64 = 0;
+            loop {
+                attempt += 1;
+                let mut hasher = FxHasher::default();
+
+                self.seed.hash(&mut hasher);
+                bin_idx.hash(&mut hasher);
+                attempt.hash(&mut hasher);
+
+                let donor_idx = self.bin_index(hasher.finish());
+                if filled[donor_idx] {
+                    bins[bin_idx] = bins[donor_idx];
+                    break;
+                }
+            }
+        }
+
+        bins
+    }
+
+    #[inline]
+    fn bin_index(&self, hash: u64) -> usize {
+        ((hash as u128 * self.num_bins as u128) >> 64) as usize
+    }
+}
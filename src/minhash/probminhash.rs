@@ -0,0 +1,65 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+use ndarray_rand::rand_distr::Exp1;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// ProbMinHash (Ertl, 2020): a locality-sensitive signature for weighted sets
+/// whose slots collide with probability equal to the probability Jaccard
+/// similarity. Each shingle draws an increasing sequence of exponentially spaced
+/// values scaled by the inverse of its weight, and each value is offered to a
+/// random slot until it can no longer lower any slot.
+#[derive(Debug)]
+pub struct ProbMinHasher {
+    seed: u64,
+    num_perm: usize,
+}
+
+impl ProbMinHasher {
+    pub fn new<R: Rng>(num_perm: usize, rand_state: &mut R) -> Self {
+        Self {
+            seed: rand_state.gen(),
+            num_perm,
+        }
+    }
+
+    pub fn signature<'a>(
+        &self,
+        weighted_shingles: impl IntoIterator<Item = (&'a u32, f64)>,
+    ) -> Vec<u64> {
+        let mut mins = vec![f64::INFINITY; self.num_perm];
+        let mut signature = vec![u64::MAX; self.num_perm];
+        if self.num_perm == 0 {
+            return signature;
+        }
+        let mut max_min = f64::INFINITY;
+
+        for (item, weight) in weighted_shingles {
+            if weight <= 0.0 || weight.is_nan() {
+                continue; // Non-positive weights are not part of the set
+            }
+            let mut hasher = FxHasher::default();
+            self.seed.hash(&mut hasher);
+            item.hash(&mut hasher);
+            let mut rng = StdRng::seed_from_u64(hasher.finish());
+
+            let mut value = rng.sample::<f64, _>(Exp1) / weight;
+            while value < max_min {
+                let slot = rng.gen_range(0..self.num_perm);
+                if value < mins[slot] {
+                    let was_max = mins[slot] == max_min;
+                    mins[slot] = value;
+                    signature[slot] = *item as u64;
+                    if was_max {
+                        max_min = mins.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                    }
+                }
+                value += rng.sample::<f64, _>(Exp1) / weight;
+            }
+        }
+
+        signature
+    }
+}
@@ -1,4 +1,4 @@
-use nohash_hasher::IntSet;
+use nohash_hasher::{IntMap, IntSet};
 use std::hash::{Hash, Hasher};
 
 use rustc_hash::FxHasher;
@@ -13,9 +13,7 @@ impl ShingleSet {
         let mut out_set: IntSet<u32> = IntSet::default();
 
         for shin in shingles {
-            let char_vec: Vec<char> = shin.chars().collect();
-            let result = Self::hash_chars(&char_vec, salt);
-            out_set.insert(result);
+            out_set.insert(Self::hash_shingle(shin, salt));
         }
 
         Self { shingles: out_set }
@@ -34,6 +32,25 @@ impl ShingleSet {
         Self { shingles: out_set }
     }
 
+    /// Number of times each shingle hash occurs in `text`.
+    pub fn shingle_counts(text: &str, ngram_width: usize, salt: Option<&str>) -> IntMap<u32, u32> {
+        let mut counts: IntMap<u32, u32> = IntMap::default();
+
+        let char_vec: Vec<char> = text.chars().collect();
+
+        for window in char_vec.windows(ngram_width) {
+            *counts.entry(Self::hash_chars(window, salt)).or_default() += 1;
+        }
+
+        counts
+    }
+
+    /// Hash of a single shingle, as stored in sets built by `from_shingles`.
+    pub fn hash_shingle(shingle: &str, salt: Option<&str>) -> u32 {
+        let char_vec: Vec<char> = shingle.chars().collect();
+        Self::hash_chars(&char_vec, salt)
+    }
+
     fn hash_chars(chars: &[char], salt: Option<&str>) -> u32 {
         let mut hasher = FxHasher::default();
 
@@ -46,6 +63,19 @@ impl ShingleSet {
         hasher.finish() as u32
     }
 
+    /// Removes `stop_shingles` from the set, unless none of its shingles would
+    /// remain, so that sets made only of stop shingles do not all collide.
+    pub fn remove_stop_shingles(&mut self, stop_shingles: &IntSet<u32>) {
+        if !self
+            .shingles
+            .iter()
+            .all(|shingle| stop_shingles.contains(shingle))
+        {
+            self.shingles
+                .retain(|shingle| !stop_shingles.contains(shingle));
+        }
+    }
+
     #[inline]
     pub fn jaccard_similarity(&self, b: &Self) -> f64 {
         if self.shingles.is_empty() | b.shingles.is_empty() {
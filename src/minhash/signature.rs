@@ -0,0 +1,132 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use rand::Rng;
+
+use super::minhasher::MinHasher;
+use super::oph::OnePermutationHasher;
+use super::probminhash::ProbMinHasher;
+use super::shingleset::ShingleSet;
+use super::superminhash::SuperMinHasher;
+
+/// Signature generators selectable through the `algorithm` parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    MinHash,
+    OnePermutation,
+    SuperMinHash,
+    ProbMinHash,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "minhash" => Ok(Self::MinHash),
+            "oph" => Ok(Self::OnePermutation),
+            "superminhash" => Ok(Self::SuperMinHash),
+            "probminhash" => Ok(Self::ProbMinHash),
+            _ => Err(format!(
+                "Unknown algorithm '{}', expected 'minhash', 'oph', 'superminhash' or 'probminhash'",
+                name
+            )),
+        }
+    }
+}
+
+impl Algorithm {
+    /// The name accepted by the `algorithm` parameter.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MinHash => "minhash",
+            Self::OnePermutation => "oph",
+            Self::SuperMinHash => "superminhash",
+            Self::ProbMinHash => "probminhash",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SignatureHasher {
+    MinHash(MinHasher),
+    OnePermutation(OnePermutationHasher),
+    SuperMinHash(SuperMinHasher),
+    ProbMinHash(ProbMinHasher),
+}
+
+impl SignatureHasher {
+    pub fn new<R: Rng>(algorithm: Algorithm, num_perm: usize, rand_state: &mut R) -> Self {
+        match algorithm {
+            Algorithm::MinHash => Self::MinHash(MinHasher::new(num_perm, rand_state)),
+            Algorithm::OnePermutation => {
+                Self::OnePermutation(OnePermutationHasher::new(num_perm, rand_state))
+            }
+            Algorithm::SuperMinHash => {
+                Self::SuperMinHash(SuperMinHasher::new(num_perm, rand_state))
+            }
+            Algorithm::ProbMinHash => Self::ProbMinHash(ProbMinHasher::new(num_perm, rand_state)),
+        }
+    }
+
+    pub fn signature(&self, shingle_set: &ShingleSet) -> Vec<u64> {
+        match self {
+            Self::MinHash(hasher) => hasher.signature(shingle_set),
+            Self::OnePermutation(hasher) => hasher.signature(shingle_set),
+            Self::SuperMinHash(hasher) => hasher.signature(shingle_set),
+            Self::ProbMinHash(hasher) => {
+                hasher.signature(shingle_set.shingles.iter().map(|item| (item, 1.0)))
+            }
+        }
+    }
+
+    /// Signature of a weighted set, where only ProbMinHash takes the weights into account.
+    pub fn weighted_signature(&self, weighted_shingles: &[(u32, f64)]) -> Result<Vec<u64>, String> {
+        match self {
+            Self::ProbMinHash(hasher) => Ok(hasher.signature(
+                weighted_shingles
+                    .iter()
+                    .map(|(item, weight)| (item, *weight)),
+            )),
+            _ => Err("Weighted input requires the 'probminhash' algorithm".to_string()),
+        }
+    }
+
+    /// Hashes each run of `band_size` signature slots into one band hash, as
+    /// `MinHasher::hash` does for a single band.
+    pub fn band_hashes(
+        &self,
+        shingle_set: &ShingleSet,
+        band_count: usize,
+        band_size: usize,
+    ) -> Vec<u64> {
+        let signature = self.signature(shingle_set);
+        (0..band_count)
+            .map(|band_idx| {
+                let mut hasher = FxHasher::default();
+                for mini_hash in &signature[band_idx * band_size..(band_idx + 1) * band_size] {
+                    mini_hash.hash(&mut hasher);
+                }
+                hasher.finish()
+            })
+            .collect()
+    }
+}
+
+/// Fraction of signature slots on which two signatures agree, the standard
+/// estimate of the Jaccard similarity of the underlying sets.
+pub fn signature_similarity(a: &[u64], b: &[u64]) -> Result<f64, String> {
+    if a.len() != b.len() {
+        return Err("Signatures must have the same length".to_string());
+    }
+    if a.is_empty() {
+        return Ok(0.0);
+    }
+    let matches = a
+        .iter()
+        .zip(b)
+        .filter(|(left, right)| left == right)
+        .count();
+    Ok(matches as f64 / a.len() as f64)
+}
@@ -0,0 +1,86 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::shingleset::ShingleSet;
+
+/// SuperMinHash (Ertl, 2017): signature slots are filled by a random
+/// permutation per shingle rather than independent hashes, which lowers the
+/// variance of the Jaccard estimate for sets not much larger than the
+/// signature.
+#[derive(Debug)]
+pub struct SuperMinHasher {
+    seed: u64,
+    num_perm: usize,
+}
+
+impl SuperMinHasher {
+    pub fn new<R: Rng>(num_perm: usize, rand_state: &mut R) -> Self {
+        Self {
+            seed: rand_state.gen(),
+            num_perm,
+        }
+    }
+
+    /// Slot values are `j + r` for the position `j` of the slot in the shingle's
+    /// permutation and a uniform `r`, returned as their IEEE 754 bits.
+    /// Variable names follow Algorithm 1 of the paper.
+    pub fn signature(&self, shingle_set: &ShingleSet) -> Vec<u64> {
+        let m = self.num_perm;
+        let mut h = vec![f64::INFINITY; m];
+        if m == 0 {
+            return vec![];
+        }
+        let mut p: Vec<usize> = (0..m).collect();
+        let mut q: Vec<Option<usize>> = vec![None; m];
+        let mut b = vec![0usize; m];
+        b[m - 1] = m;
+        let mut a = m - 1;
+
+        for (i, item) in shingle_set.shingles.iter().enumerate() {
+            let mut hasher = FxHasher::default();
+            self.seed.hash(&mut hasher);
+            item.hash(&mut hasher);
+            let mut rng = StdRng::seed_from_u64(hasher.finish());
+
+            let mut j = 0;
+            while j <= a {
+                let r: f64 = rng.gen();
+                let k = rng.gen_range(j..m);
+                if q[j] != Some(i) {
+                    q[j] = Some(i);
+                    p[j] = j;
+                }
+                if q[k] != Some(i) {
+                    q[k] = Some(i);
+                    p[k] = k;
+                }
+                p.swap(j, k);
+                if r + (j as f64) < h[p[j]] {
+                    let j_prev = (h[p[j]].floor() as usize).min(m - 1);
+                    h[p[j]] = r + j as f64;
+                    if j < j_prev {
+                        b[j_prev] -= 1;
+                        b[j] += 1;
+                        while b[a] == 0 {
+                            a -= 1;
+                        }
+                    }
+                }
+                j += 1;
+            }
+        }
+
+        h.into_iter()
+            .map(|value| {
+                if value.is_finite() {
+                    value.to_bits()
+                } else {
+                    u64::MAX
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BinaryHeap;
+use std::error::Error;
+
+use duckdb::ffi::duckdb_string_t;
+use duckdb::types::DuckString;
+use duckdb::{core::LogicalTypeId, vtab::arrow::WritableVector};
+
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+use super::minhash::shingleset::ShingleSet;
+use super::mix;
+
+/// Sampling parameters, which must be the same for every row of a group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct StratifiedSampleParams {
+    ngram_width: usize,
+    strata: usize,
+    sample_size: usize,
+    seed: u64,
+}
+
+/// A sampled pair, ordered by its hash so that every stratum keeps the pairs
+/// with the smallest hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct SampledPair {
+    hash: u64,
+    left: i64,
+    right: i64,
+    similarity_bits: u64,
+}
+
+/// Pair counts of every similarity stratum, with a uniform sample of at most
+/// `sample_size` of the pairs of each.
+#[derive(Debug, Default)]
+pub struct StratifiedSampleState {
+    params: Option<StratifiedSampleParams>,
+    counts: Vec<u64>,
+    samples: Vec<BinaryHeap<SampledPair>>,
+}
+
+impl StratifiedSampleState {
+    fn set_params(&mut self, params: StratifiedSampleParams) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => {
+                self.params = Some(params);
+                self.counts = vec![0; params.strata];
+                self.samples = vec![BinaryHeap::new(); params.strata];
+            }
+            Some(current) if current != params => {
+                return Err(
+                    "ngram_width, strata, sample_size and seed must be the same for every row"
+                        .into(),
+                )
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Keeps `pair` in the sample of `stratum` if its hash is among the
+    /// `sample_size` smallest, which samples the stratum uniformly without
+    /// replacement whatever the order of the pairs.
+    fn insert(&mut self, stratum: usize, pair: SampledPair, sample_size: usize) {
+        let sample = &mut self.samples[stratum];
+        if sample.len() < sample_size {
+            sample.push(pair);
+        } else if sample.peek().is_some_and(|largest| pair < *largest) {
+            sample.pop();
+            sample.push(pair);
+        }
+    }
+}
+
+/// Stratum of `similarity` among `strata` equal-width bins over `[0, 1]`,
+/// the last bin including 1.
+fn stratum_of(similarity: f64, strata: usize) -> usize {
+    ((similarity * strata as f64) as usize).min(strata - 1)
+}
+
+pub struct StratifiedSample {}
+
+impl VAggregate for StratifiedSample {
+    type State = StratifiedSampleState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `left` and `right` inputs
+        let input_lefts = input.flat_vector(0);
+        let lefts = input_lefts.as_slice_with_len::<i64>(input.len());
+        let input_rights = input.flat_vector(1);
+        let rights = input_rights.as_slice_with_len::<i64>(input.len());
+
+        // Prepare `string_left` and `string_right` inputs
+        let input_strings_left = input.flat_vector(2);
+        let strings_left = input_strings_left.as_slice_with_len::<duckdb_string_t>(input.len());
+        let input_strings_right = input.flat_vector(3);
+        let strings_right = input_strings_right.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare `ngram_width`, `strata`, `sample_size` and `seed` inputs
+        let params = StratifiedSampleParams {
+            ngram_width: validate_count_param(input, 4, "ngram_width")?,
+            strata: validate_count_param(input, 5, "strata")?,
+            sample_size: validate_count_param(input, 6, "sample_size")?,
+            seed: validate_count_param(input, 7, "seed")? as u64,
+        };
+        if params.ngram_width == 0 {
+            return Err("ngram_width must be positive".into());
+        }
+        if params.strata == 0 {
+            return Err("strata must be positive".into());
+        }
+
+        // Count each pair in its stratum, and sample it by its hash
+        for row_idx in 0..input.len() {
+            if input_lefts.row_is_null(row_idx as u64)
+                || input_rights.row_is_null(row_idx as u64)
+                || input_strings_left.row_is_null(row_idx as u64)
+                || input_strings_right.row_is_null(row_idx as u64)
+            {
+                continue; // Skip to the next row
+            }
+            let string_left = DuckString::new(&mut { strings_left[row_idx] })
+                .as_str()
+                .to_string();
+            let string_right = DuckString::new(&mut { strings_right[row_idx] })
+                .as_str()
+                .to_string();
+            let similarity =
+                ShingleSet::from_text(&string_left, params.ngram_width, None).jaccard_similarity(
+                    &ShingleSet::from_text(&string_right, params.ngram_width, None),
+                );
+            let (left, right) = (lefts[row_idx], rights[row_idx]);
+            let pair = SampledPair {
+                hash: mix(params.seed ^ mix(left as u64 ^ mix(right as u64))),
+                left,
+                right,
+                similarity_bits: similarity.to_bits(),
+            };
+
+            let state = &mut *states[row_idx];
+            state.set_params(params)?;
+            let stratum = stratum_of(similarity, params.strata);
+            state.counts[stratum] += 1;
+            state.insert(stratum, pair, params.sample_size);
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            for (stratum, sample) in source.samples.drain(..).enumerate() {
+                target.counts[stratum] += source.counts[stratum];
+                for pair in sample {
+                    target.insert(stratum, pair, params.sample_size);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Sampled pairs of each group by stratum, with the stratum sizes
+        let row_pairs: Vec<Option<Vec<(usize, u64, SampledPair)>>> = states
+            .iter()
+            .map(|state| {
+                state.params?;
+                let mut pairs: Vec<(usize, u64, SampledPair)> = state
+                    .samples
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(stratum, sample)| {
+                        let count = state.counts[stratum];
+                        sample.iter().map(move |&pair| (stratum, count, pair))
+                    })
+                    .collect();
+                pairs.sort_unstable_by_key(|&(stratum, _, pair)| (stratum, pair.left, pair.right));
+                Some(pairs)
+            })
+            .collect();
+
+        // Prepare output, after the entries of earlier finalized states
+        let mut output_pairs = output.list_vector();
+        let pairs_offset = output_pairs.len();
+        let pairs_len_sum: usize =
+            pairs_offset + row_pairs.iter().flatten().map(|p| p.len()).sum::<usize>();
+        let pairs_vec = output_pairs.struct_child(pairs_len_sum);
+        let mut lefts_vec = pairs_vec.child(0, pairs_len_sum);
+        let lefts: &mut [i64] = lefts_vec.as_mut_slice_with_len(pairs_len_sum);
+        let mut rights_vec = pairs_vec.child(1, pairs_len_sum);
+        let rights: &mut [i64] = rights_vec.as_mut_slice_with_len(pairs_len_sum);
+        let mut similarities_vec = pairs_vec.child(2, pairs_len_sum);
+        let similarities: &mut [f64] = similarities_vec.as_mut_slice_with_len(pairs_len_sum);
+        let mut strata_vec = pairs_vec.child(3, pairs_len_sum);
+        let strata: &mut [i64] = strata_vec.as_mut_slice_with_len(pairs_len_sum);
+        let mut sizes_vec = pairs_vec.child(4, pairs_len_sum);
+        let sizes: &mut [i64] = sizes_vec.as_mut_slice_with_len(pairs_len_sum);
+        let mut weights_vec = pairs_vec.child(5, pairs_len_sum);
+        let weights: &mut [f64] = weights_vec.as_mut_slice_with_len(pairs_len_sum);
+
+        // Write output, weighting each pair by the inverse of the share of its
+        // stratum that was sampled
+        let mut pair_offset = pairs_offset;
+        for (state_idx, (pairs, state)) in row_pairs.iter().zip(states.iter()).enumerate() {
+            let Some(pairs) = pairs else {
+                output_pairs.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            for (pair_idx, &(stratum, count, pair)) in pairs.iter().enumerate() {
+                let idx = pair_offset + pair_idx;
+                lefts[idx] = pair.left;
+                rights[idx] = pair.right;
+                similarities[idx] = f64::from_bits(pair.similarity_bits);
+                strata[idx] = stratum as i64;
+                sizes[idx] = count as i64;
+                weights[idx] = count as f64 / state.samples[stratum].len() as f64;
+            }
+            output_pairs.set_entry(offset + state_idx, pair_offset, pairs.len());
+            pair_offset += pairs.len();
+        }
+        output_pairs.set_len(pair_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+                LogicalTypeId::Bigint.into(),
+            ],
+            AggregateType::list(AggregateType::struct_type(vec![
+                ("left", LogicalTypeId::Bigint.into()),
+                ("right", LogicalTypeId::Bigint.into()),
+                ("similarity", LogicalTypeId::Double.into()),
+                ("stratum", LogicalTypeId::Bigint.into()),
+                ("stratum_size", LogicalTypeId::Bigint.into()),
+                ("weight", LogicalTypeId::Double.into()),
+            ])),
+        )]
+    }
+}
@@ -0,0 +1,323 @@
+use std::error::Error;
+
+use rustc_hash::FxHashMap;
+
+use duckdb::ffi::{duckdb_list_entry, duckdb_string_t};
+use duckdb::types::DuckString;
+use duckdb::{
+    core::{DataChunkHandle, FlatVector, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+    Result,
+};
+
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+use super::BlobCache;
+
+pub mod fellegi_sunter;
+
+use fellegi_sunter::{FellegiSunter, Pattern};
+
+/// Number of EM iterations when `max_iterations` is not given
+const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+/// Highest comparison level, which bounds the probability tables of a model
+const MAX_LEVEL: u32 = 255;
+
+/// Reads the comparison levels of a row, with `None` for `NULL` levels.
+fn read_levels(
+    levels: &[i64],
+    levels_vec: &FlatVector,
+    meta: &duckdb_list_entry,
+) -> Result<Vec<Option<u32>>, Box<dyn Error>> {
+    let offset = meta.offset as usize;
+    (offset..(offset + meta.length as usize))
+        .map(|level_idx| {
+            if levels_vec.row_is_null(level_idx as u64) {
+                return Ok(None);
+            }
+            let level = u32::try_from(levels[level_idx])
+                .map_err(|_| "Comparison levels must not be negative")?;
+            if level > MAX_LEVEL {
+                return Err(format!("Comparison levels must not exceed {}", MAX_LEVEL).into());
+            }
+            Ok(Some(level))
+        })
+        .collect()
+}
+
+/// Counts of the distinct comparison vectors of a group's pairs, with the
+/// number of fields and EM iterations of the model fitted to them.
+#[derive(Debug, Default)]
+pub struct FellegiSunterFitState {
+    params: Option<(usize, usize)>,
+    patterns: FxHashMap<Vec<Option<u32>>, u64>,
+}
+
+impl FellegiSunterFitState {
+    fn set_params(&mut self, params: (usize, usize)) -> Result<(), Box<dyn Error>> {
+        match self.params {
+            None => self.params = Some(params),
+            Some(current) if current.0 != params.0 => {
+                return Err("Comparison vectors must have the same length in every row".into())
+            }
+            Some(current) if current != params => {
+                return Err("max_iterations must be the same for every row".into())
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
+}
+
+pub struct FellegiSunterFit {}
+
+impl VAggregate for FellegiSunterFit {
+    type State = FellegiSunterFitState;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare comparison level array input
+        let input_arrays_meta = input.flat_vector(0);
+        let input_arrays_data = input.list_vector(0);
+        let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+        let arrays: &[i64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+        // Prepare `max_iterations` input
+        let max_iterations = if input.num_columns() > 1 {
+            validate_count_param(input, 1, "max_iterations")?
+        } else {
+            DEFAULT_MAX_ITERATIONS
+        };
+
+        // Count each row's comparison vector in its group
+        for (row_idx, meta) in arrays_meta.iter().enumerate() {
+            if input_arrays_meta.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let levels = read_levels(arrays, &arrays_vec, meta)?;
+            let state = &mut *states[row_idx];
+            state.set_params((levels.len(), max_iterations))?;
+            *state.patterns.entry(levels).or_default() += 1;
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        if let Some(params) = source.params {
+            target.set_params(params)?;
+            for (levels, count) in source.patterns.drain() {
+                *target.patterns.entry(levels).or_default() += count;
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut output_blobs = output.flat_vector();
+        for (state_idx, state) in states.iter_mut().enumerate() {
+            let Some((_, max_iterations)) = state.params else {
+                output_blobs.set_null(offset + state_idx);
+                continue; // Skip to the next state
+            };
+            // Sorted, so that the model does not depend on the order of the rows
+            let mut patterns: Vec<Pattern> = state.patterns.drain().collect();
+            patterns.sort_unstable();
+            let model = FellegiSunter::fit(&patterns, max_iterations)?;
+            output_blobs.insert(offset + state_idx, model.to_bytes().as_slice());
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![
+            AggregateFunctionSignature::exact(
+                vec![AggregateType::list(LogicalTypeId::Bigint.into())],
+                LogicalTypeId::Blob.into(),
+            ),
+            AggregateFunctionSignature::exact(
+                vec![
+                    AggregateType::list(LogicalTypeId::Bigint.into()),
+                    LogicalTypeId::Bigint.into(),
+                ],
+                LogicalTypeId::Blob.into(),
+            ),
+        ]
+    }
+}
+
+pub struct FellegiSunterScore {}
+
+impl VScalar for FellegiSunterScore {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare comparison level array input
+        let input_arrays_meta = input.flat_vector(0);
+        let input_arrays_data = input.list_vector(0);
+        let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+        let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+        let arrays: &[i64] = arrays_vec.as_slice_with_len(input_arrays_data.len());
+
+        // Prepare `model` input
+        let input_blobs = input.flat_vector(1);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Prepare output
+        let mut output_scores = output.struct_vector();
+        let mut weights_vec = output_scores.child(0, input.len());
+        let mut probabilities_vec = output_scores.child(1, input.len());
+
+        // Score with the model, which is usually the same for every row
+        let mut model: BlobCache<FellegiSunter> = BlobCache::default();
+        for (row_idx, (meta, blob)) in arrays_meta.iter().zip(blobs).enumerate() {
+            if input_arrays_meta.row_is_null(row_idx as u64)
+                || input_blobs.row_is_null(row_idx as u64)
+            {
+                output_scores.set_null(row_idx);
+                weights_vec.set_null(row_idx);
+                probabilities_vec.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+            let mut blob = *blob;
+            let fellegi_sunter = &*model.get_or_decode(
+                DuckString::new(&mut blob).as_bytes(),
+                FellegiSunter::from_bytes,
+            )?;
+
+            let levels = read_levels(arrays, &arrays_vec, meta)?;
+            let weight = fellegi_sunter.match_weight(&levels)?;
+            weights_vec.as_mut_slice_with_len::<f64>(input.len())[row_idx] = weight;
+            probabilities_vec.as_mut_slice_with_len::<f64>(input.len())[row_idx] =
+                FellegiSunter::match_probability_of(weight);
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeId::Bigint.into()),
+                LogicalTypeId::Blob.into(),
+            ],
+            LogicalTypeHandle::struct_type(&[
+                ("match_weight", LogicalTypeId::Double.into()),
+                ("match_probability", LogicalTypeId::Double.into()),
+            ]),
+        )]
+    }
+}
+
+pub struct FellegiSunterParams {}
+
+impl VScalar for FellegiSunterParams {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `model` input
+        let input_blobs = input.flat_vector(0);
+        let models = input_blobs
+            .as_slice_with_len::<duckdb_string_t>(input.len())
+            .iter()
+            .enumerate()
+            .map(|(row_idx, blob)| {
+                if input_blobs.row_is_null(row_idx as u64) {
+                    return Ok(None);
+                }
+                FellegiSunter::from_bytes(DuckString::new(&mut { *blob }).as_bytes()).map(Some)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Prepare output
+        let mut output_params = output.struct_vector();
+        let mut match_probabilities_vec = output_params.child(0, input.len());
+        let mut iteration_counts_vec = output_params.child(1, input.len());
+        let mut output_levels = output_params.list_vector_child(2);
+        let levels_len_sum: usize = models
+            .iter()
+            .flatten()
+            .map(|model| model.m.iter().map(|levels| levels.len()).sum::<usize>())
+            .sum();
+        let levels_vec = output_levels.struct_child(levels_len_sum);
+        let mut fields_vec = levels_vec.child(0, levels_len_sum);
+        let fields: &mut [i64] = fields_vec.as_mut_slice_with_len(levels_len_sum);
+        let mut level_idxs_vec = levels_vec.child(1, levels_len_sum);
+        let level_idxs: &mut [i64] = level_idxs_vec.as_mut_slice_with_len(levels_len_sum);
+        let mut ms_vec = levels_vec.child(2, levels_len_sum);
+        let ms: &mut [f64] = ms_vec.as_mut_slice_with_len(levels_len_sum);
+        let mut us_vec = levels_vec.child(3, levels_len_sum);
+        let us: &mut [f64] = us_vec.as_mut_slice_with_len(levels_len_sum);
+        let mut weights_vec = levels_vec.child(4, levels_len_sum);
+        let weights: &mut [f64] = weights_vec.as_mut_slice_with_len(levels_len_sum);
+
+        // Write output
+        let mut level_offset = 0;
+        for (row_idx, model) in models.iter().enumerate() {
+            let Some(model) = model else {
+                output_params.set_null(row_idx);
+                match_probabilities_vec.set_null(row_idx);
+                iteration_counts_vec.set_null(row_idx);
+                output_levels.set_null(row_idx);
+                continue; // Skip to the next row
+            };
+            match_probabilities_vec.as_mut_slice_with_len::<f64>(input.len())[row_idx] =
+                model.match_probability;
+            iteration_counts_vec.as_mut_slice_with_len::<i64>(input.len())[row_idx] =
+                model.iteration_count as i64;
+            let row_offset = level_offset;
+            for (field, (m_levels, u_levels)) in model.m.iter().zip(&model.u).enumerate() {
+                for (level, (&m, &u)) in m_levels.iter().zip(u_levels).enumerate() {
+                    fields[level_offset] = field as i64 + 1;
+                    level_idxs[level_offset] = level as i64;
+                    ms[level_offset] = m;
+                    us[level_offset] = u;
+                    weights[level_offset] = (m / u).log2();
+                    level_offset += 1;
+                }
+            }
+            output_levels.set_entry(row_idx, row_offset, level_offset - row_offset);
+        }
+        output_levels.set_len(level_offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into()],
+            LogicalTypeHandle::struct_type(&[
+                ("match_probability", LogicalTypeId::Double.into()),
+                ("iteration_count", LogicalTypeId::Bigint.into()),
+                (
+                    "levels",
+                    LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                        ("field", LogicalTypeId::Bigint.into()),
+                        ("level", LogicalTypeId::Bigint.into()),
+                        ("m_probability", LogicalTypeId::Double.into()),
+                        ("u_probability", LogicalTypeId::Double.into()),
+                        ("match_weight", LogicalTypeId::Double.into()),
+                    ])),
+                ),
+            ]),
+        )]
+    }
+}
@@ -0,0 +1,247 @@
+/// A Fellegi–Sunter model of record pairs: with the prior `match_probability`
+/// a pair is a match, and each field of a match agrees at level `l` with
+/// probability `m[field][l]`, or of a non-match with `u[field][l]`, the fields
+/// being independent given the match status.
+///
+/// Higher levels are taken to mean closer agreement, which is only used to
+/// start the EM algorithm with the matches agreeing more than the non-matches,
+/// so that the fitted classes are not swapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FellegiSunter {
+    pub match_probability: f64,
+    pub m: Vec<Vec<f64>>,
+    pub u: Vec<Vec<f64>>,
+    pub iteration_count: usize,
+}
+
+/// Comparison levels of a pair, with `None` for a field that cannot be
+/// compared, and the number of pairs with these levels.
+pub type Pattern = (Vec<Option<u32>>, u64);
+
+impl FellegiSunter {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 1 + 8 + 4 + 4;
+    const INITIAL_MATCH_PROBABILITY: f64 = 0.1;
+    const TOLERANCE: f64 = 1e-9;
+    /// Pseudo-count keeping probabilities away from 0, so that every level seen
+    /// has a finite weight
+    const SMOOTHING: f64 = 1e-6;
+
+    /// Fits the model to the `patterns` of the pairs with the EM algorithm,
+    /// stopping when no probability changes by more than `TOLERANCE` or after
+    /// `max_iterations` iterations.
+    pub fn fit(patterns: &[Pattern], max_iterations: usize) -> Result<Self, String> {
+        let field_count = patterns.first().map_or(0, |(levels, _)| levels.len());
+        if field_count == 0 {
+            return Err("Comparison vectors must not be empty".to_string());
+        }
+        let level_counts: Vec<usize> = (0..field_count)
+            .map(|field| {
+                patterns
+                    .iter()
+                    .filter_map(|(levels, _)| levels[field])
+                    .max()
+                    .map_or(1, |level| level as usize + 1)
+            })
+            .collect();
+
+        // Matches start out likelier at higher levels and non-matches at lower ones
+        let initial = |rising: bool| -> Vec<Vec<f64>> {
+            level_counts
+                .iter()
+                .map(|&count| {
+                    let total = (count * (count + 1) / 2) as f64;
+                    (0..count)
+                        .map(|level| {
+                            let rank = if rising { level + 1 } else { count - level };
+                            rank as f64 / total
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        let mut model = Self {
+            match_probability: Self::INITIAL_MATCH_PROBABILITY,
+            m: initial(true),
+            u: initial(false),
+            iteration_count: 0,
+        };
+
+        while model.iteration_count < max_iterations {
+            // E-step: expected match status of every pattern
+            let posteriors: Vec<f64> = patterns
+                .iter()
+                .map(|(levels, _)| model.posterior(levels))
+                .collect();
+
+            // M-step: probabilities maximizing the expected likelihood
+            let (mut match_counts, mut non_match_counts) = (
+                level_counts
+                    .iter()
+                    .map(|&c| vec![0.0; c])
+                    .collect::<Vec<_>>(),
+                level_counts
+                    .iter()
+                    .map(|&c| vec![0.0; c])
+                    .collect::<Vec<_>>(),
+            );
+            let (mut matches, mut total) = (0.0, 0.0);
+            for ((levels, count), &posterior) in patterns.iter().zip(&posteriors) {
+                let count = *count as f64;
+                matches += count * posterior;
+                total += count;
+                for (field, level) in levels.iter().enumerate() {
+                    if let Some(level) = level {
+                        match_counts[field][*level as usize] += count * posterior;
+                        non_match_counts[field][*level as usize] += count * (1.0 - posterior);
+                    }
+                }
+            }
+            let normalize = |counts: Vec<Vec<f64>>| -> Vec<Vec<f64>> {
+                counts
+                    .into_iter()
+                    .map(|counts| {
+                        let sum: f64 = counts.iter().sum();
+                        let smoothing = Self::SMOOTHING * counts.len() as f64;
+                        counts
+                            .iter()
+                            .map(|count| (count + Self::SMOOTHING) / (sum + smoothing))
+                            .collect()
+                    })
+                    .collect()
+            };
+            let next = Self {
+                match_probability: (matches / total).clamp(Self::SMOOTHING, 1.0 - Self::SMOOTHING),
+                m: normalize(match_counts),
+                u: normalize(non_match_counts),
+                iteration_count: model.iteration_count + 1,
+            };
+            let change = next.max_change(&model);
+            model = next;
+            if change < Self::TOLERANCE {
+                break;
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Largest absolute difference between the probabilities of two models
+    /// with the same levels.
+    fn max_change(&self, other: &Self) -> f64 {
+        let changes = |a: &[Vec<f64>], b: &[Vec<f64>]| -> f64 {
+            a.iter()
+                .flatten()
+                .zip(b.iter().flatten())
+                .map(|(x, y)| (x - y).abs())
+                .fold(0.0, f64::max)
+        };
+        (self.match_probability - other.match_probability)
+            .abs()
+            .max(changes(&self.m, &other.m))
+            .max(changes(&self.u, &other.u))
+    }
+
+    /// Probability that a pair with `levels` is a match, for levels the model
+    /// was fitted to.
+    fn posterior(&self, levels: &[Option<u32>]) -> f64 {
+        let (mut log_match, mut log_non_match) = (
+            self.match_probability.ln(),
+            (1.0 - self.match_probability).ln(),
+        );
+        for (field, level) in levels.iter().enumerate() {
+            if let Some(level) = level {
+                log_match += self.m[field][*level as usize].ln();
+                log_non_match += self.u[field][*level as usize].ln();
+            }
+        }
+        1.0 / (1.0 + (log_non_match - log_match).exp())
+    }
+
+    /// Match weight of a pair with `levels`: the log2 of the prior odds of a
+    /// match plus the log2 Bayes factor `m / u` of every comparable field.
+    pub fn match_weight(&self, levels: &[Option<u32>]) -> Result<f64, String> {
+        if levels.len() != self.m.len() {
+            return Err(format!(
+                "Comparison vectors must have {} levels, as the vectors the model was fitted to",
+                self.m.len()
+            ));
+        }
+        let mut weight = (self.match_probability / (1.0 - self.match_probability)).log2();
+        for (field, level) in levels.iter().enumerate() {
+            let Some(level) = level else {
+                continue;
+            };
+            let (Some(m), Some(u)) = (
+                self.m[field].get(*level as usize),
+                self.u[field].get(*level as usize),
+            ) else {
+                return Err(format!(
+                    "Comparison level {} of field {} was not seen when fitting the model",
+                    level,
+                    field + 1
+                ));
+            };
+            weight += (m / u).log2();
+        }
+        Ok(weight)
+    }
+
+    /// Probability of a match with the given match weight.
+    pub fn match_probability_of(match_weight: f64) -> f64 {
+        1.0 / (1.0 + (-match_weight).exp2())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&self.match_probability.to_le_bytes());
+        bytes.extend_from_slice(&(self.iteration_count as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.m.len() as u32).to_le_bytes());
+        for levels in &self.m {
+            bytes.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+        }
+        for value in self.m.iter().chain(&self.u).flatten() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid Fellegi-Sunter model".to_string();
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(invalid());
+        }
+        let match_probability = f64::from_le_bytes(bytes[1..9].try_into().unwrap());
+        let iteration_count = u32::from_le_bytes(bytes[9..13].try_into().unwrap()) as usize;
+        let field_count = u32::from_le_bytes(bytes[13..17].try_into().unwrap()) as usize;
+        let levels_end = Self::HEADER_LEN + 4 * field_count;
+        if bytes.len() < levels_end {
+            return Err(invalid());
+        }
+        let level_counts: Vec<usize> = bytes[Self::HEADER_LEN..levels_end]
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()) as usize)
+            .collect();
+        let level_sum: usize = level_counts.iter().sum();
+        if bytes.len() != levels_end + 2 * 8 * level_sum {
+            return Err(invalid());
+        }
+        let mut values = bytes[levels_end..]
+            .chunks_exact(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()));
+        let mut read = || -> Vec<Vec<f64>> {
+            level_counts
+                .iter()
+                .map(|&count| values.by_ref().take(count).collect())
+                .collect()
+        };
+        let (m, u) = (read(), read());
+        Ok(Self {
+            match_probability,
+            m,
+            u,
+            iteration_count,
+        })
+    }
+}
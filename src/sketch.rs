@@ -0,0 +1,633 @@
+use std::error::Error;
+
+use duckdb::ffi::{duckdb_list_entry, duckdb_string_t};
+use duckdb::types::DuckString;
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+    Result,
+};
+
+use super::aggregate::{
+    validate_count_param, AggregateFunctionSignature, AggregateInput, AggregateType, VAggregate,
+};
+use super::minhash::shingleset::ShingleSet;
+use super::validate_constant_param;
+
+pub mod bottomk;
+pub mod hyperminhash;
+
+use bottomk::BottomK;
+use hyperminhash::HyperMinHash;
+
+unsafe fn hyperminhash_from_text(
+    input: &AggregateInput,
+    states: &[*mut Option<HyperMinHash>],
+) -> Result<(), Box<dyn Error>> {
+    // Prepare text input
+    let input_strings = input.flat_vector(0);
+    let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Prepare `ngram_width` input
+    let ngram_width = validate_count_param(input, 1, "ngram_width")?;
+    if ngram_width == 0 {
+        return Err("ngram_width must be positive".into());
+    }
+
+    // Add each row to its group's sketch
+    for (row_idx, string) in strings.iter().enumerate() {
+        if input_strings.row_is_null(row_idx as u64) {
+            continue; // Skip to the next row
+        }
+        let string = DuckString::new(&mut { *string }).as_str().to_string();
+        let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+        let state = &mut *states[row_idx];
+        state
+            .get_or_insert_with(HyperMinHash::default)
+            .insert_set(&shingle_set);
+    }
+
+    Ok(())
+}
+
+unsafe fn hyperminhash_from_shingles(
+    input: &AggregateInput,
+    states: &[*mut Option<HyperMinHash>],
+) -> Result<(), Box<dyn Error>> {
+    // Prepare shingles array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: Vec<String> = arrays_vec
+        .as_slice_with_len::<duckdb_string_t>(input_arrays_data.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Add each row to its group's sketch
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            continue; // Skip to the next row
+        }
+
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        let arr_refs: Vec<&str> = arr.iter().map(|s| s.as_str()).collect();
+        let shingle_set = ShingleSet::from_shingles(&arr_refs, None);
+
+        let state = &mut *states[row_idx];
+        state
+            .get_or_insert_with(HyperMinHash::default)
+            .insert_set(&shingle_set);
+    }
+
+    Ok(())
+}
+
+/// Merges `source` into `target`, leaving `target` untouched if `source` saw no rows.
+fn merge_sketch_states(
+    source: &Option<HyperMinHash>,
+    target: &mut Option<HyperMinHash>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(source) = source {
+        match target {
+            Some(target) => target.merge(source),
+            None => *target = Some(source.clone()),
+        }
+    }
+    Ok(())
+}
+
+unsafe fn finalize_sketches<S>(
+    states: &mut [&mut Option<S>],
+    offset: usize,
+    output: &mut dyn WritableVector,
+    to_bytes: fn(&S) -> Vec<u8>,
+) -> Result<(), Box<dyn Error>> {
+    let mut output_blobs = output.flat_vector();
+    for (state_idx, state) in states.iter().enumerate() {
+        match state {
+            Some(sketch) => output_blobs.insert(offset + state_idx, to_bytes(sketch).as_slice()),
+            None => output_blobs.set_null(offset + state_idx),
+        }
+    }
+    Ok(())
+}
+
+pub struct HyperMinHashSketch {}
+
+impl VAggregate for HyperMinHashSketch {
+    type State = Option<HyperMinHash>;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::Varchar => hyperminhash_from_text(input, states),
+            LogicalTypeId::List => hyperminhash_from_shingles(input, states),
+            _ => Err("Unsupported argument type for HyperMinHash sketch".into()),
+        }
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        merge_sketch_states(source, target)
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        finalize_sketches(states, offset, output, HyperMinHash::to_bytes)
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![
+            AggregateFunctionSignature::exact(
+                vec![LogicalTypeId::Varchar.into(), LogicalTypeId::Bigint.into()],
+                LogicalTypeId::Blob.into(),
+            ),
+            AggregateFunctionSignature::exact(
+                vec![AggregateType::list(LogicalTypeId::Varchar.into())],
+                LogicalTypeId::Blob.into(),
+            ),
+        ]
+    }
+}
+
+pub struct HyperMinHashUnion {}
+
+impl VAggregate for HyperMinHashUnion {
+    type State = Option<HyperMinHash>;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `sketches` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Merge each row into its group's sketch
+        for (row_idx, blob) in blobs.iter().enumerate() {
+            if input_blobs.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let sketch = HyperMinHash::from_bytes(DuckString::new(&mut { *blob }).as_bytes())?;
+            let state = &mut *states[row_idx];
+            match state {
+                Some(target) => target.merge(&sketch),
+                None => *state = Some(sketch),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        merge_sketch_states(source, target)
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        finalize_sketches(states, offset, output, HyperMinHash::to_bytes)
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into()],
+            LogicalTypeId::Blob.into(),
+        )]
+    }
+}
+
+pub struct HyperMinHashCardinality {}
+
+impl VScalar for HyperMinHashCardinality {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `sketches` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Estimate the cardinality of each sketch
+        let mut output_estimates = output.flat_vector();
+        for (row_idx, blob) in blobs.iter().enumerate() {
+            if input_blobs.row_is_null(row_idx as u64) {
+                output_estimates.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+
+            let sketch = HyperMinHash::from_bytes(DuckString::new(&mut { *blob }).as_bytes())?;
+
+            let estimates = output_estimates.as_mut_slice_with_len::<f64>(input.len());
+            estimates[row_idx] = sketch.cardinality();
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into()],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+unsafe fn compare_hyperminhash(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    measure: fn(&HyperMinHash, &HyperMinHash) -> f64,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare `sketches_left` input
+    let input_blobs_left = input.flat_vector(0);
+    let blobs_left = input_blobs_left.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Prepare `sketches_right` input
+    let input_blobs_right = input.flat_vector(1);
+    let blobs_right = input_blobs_right.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Compare each pair of sketches
+    let mut output_measures = output.flat_vector();
+    for (row_idx, (b_left, b_right)) in blobs_left.iter().zip(blobs_right).enumerate() {
+        if input_blobs_left.row_is_null(row_idx as u64)
+            || input_blobs_right.row_is_null(row_idx as u64)
+        {
+            output_measures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let sketch_left = HyperMinHash::from_bytes(DuckString::new(&mut { *b_left }).as_bytes())?;
+        let sketch_right = HyperMinHash::from_bytes(DuckString::new(&mut { *b_right }).as_bytes())?;
+
+        let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+        measures[row_idx] = measure(&sketch_left, &sketch_right);
+    }
+
+    Ok(())
+}
+
+fn sketch_pair_signatures() -> Vec<ScalarFunctionSignature> {
+    vec![ScalarFunctionSignature::exact(
+        vec![LogicalTypeId::Blob.into(), LogicalTypeId::Blob.into()],
+        LogicalTypeId::Double.into(),
+    )]
+}
+
+pub struct HyperMinHashJaccard {}
+
+impl VScalar for HyperMinHashJaccard {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        compare_hyperminhash(input, output, HyperMinHash::jaccard_similarity)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        sketch_pair_signatures()
+    }
+}
+
+pub struct HyperMinHashIntersection {}
+
+impl VScalar for HyperMinHashIntersection {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        compare_hyperminhash(input, output, HyperMinHash::intersection)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        sketch_pair_signatures()
+    }
+}
+
+fn validate_k_param(k: usize) -> Result<usize, Box<dyn Error>> {
+    match k {
+        0 => Err("k must be positive".into()),
+        k if k < BottomK::MIN_K => Err(format!("k must be at least {}", BottomK::MIN_K).into()),
+        k => Ok(k),
+    }
+}
+
+unsafe fn bottomk_from_text(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare text input
+    let input_strings = input.flat_vector(0);
+    let strings = input_strings.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Prepare `ngram_width` input
+    let ngram_width = validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "ngram_width",
+    )?;
+    if ngram_width == 0 {
+        return Err("ngram_width must be positive".into());
+    }
+
+    // Prepare `k` input
+    let k = validate_k_param(validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<usize>(input.len()),
+        "k",
+    )?)?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(3).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Perform sketching
+    let mut output_blobs = output.flat_vector();
+    for (row_idx, string) in strings.iter().enumerate() {
+        if input_strings.row_is_null(row_idx as u64) {
+            output_blobs.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+        let string = DuckString::new(&mut { *string }).as_str().to_string();
+        let shingle_set = ShingleSet::from_text(&string, ngram_width, None);
+        let sketch = BottomK::from_set(&shingle_set, k, seed);
+        output_blobs.insert(row_idx, sketch.to_bytes().as_slice());
+    }
+
+    Ok(())
+}
+
+unsafe fn bottomk_from_shingles(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare shingles array input
+    let input_arrays_meta = input.flat_vector(0);
+    let input_arrays_data = input.list_vector(0);
+    let arrays_meta = input_arrays_meta.as_slice_with_len::<duckdb_list_entry>(input.len());
+    let arrays_vec = input_arrays_data.child(input_arrays_data.len());
+    let arrays: Vec<String> = arrays_vec
+        .as_slice_with_len::<duckdb_string_t>(input_arrays_data.len())
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Prepare `k` input
+    let k = validate_k_param(validate_constant_param(
+        input.flat_vector(1).as_slice_with_len::<usize>(input.len()),
+        "k",
+    )?)?;
+
+    // Prepare `seed` input
+    let seed = validate_constant_param(
+        input.flat_vector(2).as_slice_with_len::<u64>(input.len()),
+        "seed",
+    )?;
+
+    // Perform sketching
+    let mut output_blobs = output.flat_vector();
+    for (row_idx, meta) in arrays_meta.iter().enumerate() {
+        if input_arrays_meta.row_is_null(row_idx as u64) {
+            output_blobs.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let arr_offset = meta.offset as usize;
+        let arr_length = meta.length as usize;
+        let arr = &arrays[arr_offset..(arr_offset + arr_length)];
+        let arr_refs: Vec<&str> = arr.iter().map(|s| s.as_str()).collect();
+        let shingle_set = ShingleSet::from_shingles(&arr_refs, None);
+
+        let sketch = BottomK::from_set(&shingle_set, k, seed);
+        output_blobs.insert(row_idx, sketch.to_bytes().as_slice());
+    }
+
+    Ok(())
+}
+
+pub struct BottomKSketch {}
+
+impl VScalar for BottomKSketch {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        match input.flat_vector(0).logical_type().id() {
+            LogicalTypeId::Varchar => bottomk_from_text(input, output),
+            LogicalTypeId::List => bottomk_from_shingles(input, output),
+            _ => Err("Unsupported argument type for bottom-k sketch".into()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Blob.into(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeId::Varchar.into()),
+                    LogicalTypeId::UBigint.into(),
+                    LogicalTypeId::UBigint.into(),
+                ],
+                LogicalTypeId::Blob.into(),
+            ),
+        ]
+    }
+}
+
+/// Replaces `target` with the union of both sketches, leaving it untouched if
+/// `source` saw no rows.
+fn union_bottomk_states(
+    source: &Option<BottomK>,
+    target: &mut Option<BottomK>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(source) = source {
+        *target = Some(match target {
+            Some(target) => target.union(source)?,
+            None => source.clone(),
+        });
+    }
+    Ok(())
+}
+
+pub struct BottomKUnion {}
+
+impl VAggregate for BottomKUnion {
+    type State = Option<BottomK>;
+
+    unsafe fn update(
+        input: &AggregateInput,
+        states: &[*mut Self::State],
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `sketches` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Merge each row into its group's sketch
+        for (row_idx, blob) in blobs.iter().enumerate() {
+            if input_blobs.row_is_null(row_idx as u64) {
+                continue; // Skip to the next row
+            }
+            let sketch = BottomK::from_bytes(DuckString::new(&mut { *blob }).as_bytes())?;
+            union_bottomk_states(&Some(sketch), &mut *states[row_idx])?;
+        }
+
+        Ok(())
+    }
+
+    fn combine(source: &mut Self::State, target: &mut Self::State) -> Result<(), Box<dyn Error>> {
+        union_bottomk_states(source, target)
+    }
+
+    unsafe fn finalize(
+        states: &mut [&mut Self::State],
+        offset: usize,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        finalize_sketches(states, offset, output, BottomK::to_bytes)
+    }
+
+    fn signatures() -> Vec<AggregateFunctionSignature> {
+        vec![AggregateFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into()],
+            LogicalTypeId::Blob.into(),
+        )]
+    }
+}
+
+pub struct BottomKCardinality {}
+
+impl VScalar for BottomKCardinality {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        // Prepare `sketches` input
+        let input_blobs = input.flat_vector(0);
+        let blobs = input_blobs.as_slice_with_len::<duckdb_string_t>(input.len());
+
+        // Estimate the cardinality of each sketch
+        let mut output_estimates = output.flat_vector();
+        for (row_idx, blob) in blobs.iter().enumerate() {
+            if input_blobs.row_is_null(row_idx as u64) {
+                output_estimates.set_null(row_idx);
+                continue; // Skip to the next row
+            }
+
+            let sketch = BottomK::from_bytes(DuckString::new(&mut { *blob }).as_bytes())?;
+
+            let estimates = output_estimates.as_mut_slice_with_len::<f64>(input.len());
+            estimates[row_idx] = sketch.cardinality();
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Blob.into()],
+            LogicalTypeId::Double.into(),
+        )]
+    }
+}
+
+unsafe fn compare_bottomk(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    measure: fn(&BottomK, &BottomK) -> Result<f64, String>,
+) -> Result<(), Box<dyn Error>> {
+    // Prepare `sketches_left` input
+    let input_blobs_left = input.flat_vector(0);
+    let blobs_left = input_blobs_left.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Prepare `sketches_right` input
+    let input_blobs_right = input.flat_vector(1);
+    let blobs_right = input_blobs_right.as_slice_with_len::<duckdb_string_t>(input.len());
+
+    // Compare each pair of sketches
+    let mut output_measures = output.flat_vector();
+    for (row_idx, (b_left, b_right)) in blobs_left.iter().zip(blobs_right).enumerate() {
+        if input_blobs_left.row_is_null(row_idx as u64)
+            || input_blobs_right.row_is_null(row_idx as u64)
+        {
+            output_measures.set_null(row_idx);
+            continue; // Skip to the next row
+        }
+
+        let sketch_left = BottomK::from_bytes(DuckString::new(&mut { *b_left }).as_bytes())?;
+        let sketch_right = BottomK::from_bytes(DuckString::new(&mut { *b_right }).as_bytes())?;
+
+        let measures = output_measures.as_mut_slice_with_len::<f64>(input.len());
+        measures[row_idx] = measure(&sketch_left, &sketch_right)?;
+    }
+
+    Ok(())
+}
+
+pub struct BottomKJaccard {}
+
+impl VScalar for BottomKJaccard {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        compare_bottomk(input, output, BottomK::jaccard_similarity)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        sketch_pair_signatures()
+    }
+}
+
+pub struct BottomKContainment {}
+
+impl VScalar for BottomKContainment {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        compare_bottomk(input, output, BottomK::containment)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        sketch_pair_signatures()
+    }
+}
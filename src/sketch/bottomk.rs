@@ -0,0 +1,176 @@
+use rustc_hash::FxHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::minhash::shingleset::ShingleSet;
+use crate::mix;
+
+/// Bottom-k sketch: the `k` smallest hashes of a set under one seeded hash
+/// function. Sketches of sets with fewer than `k` shingles hold every hash, so
+/// estimates involving only such sets are exact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BottomK {
+    k: usize,
+    seed: u64,
+    hashes: Vec<u64>,
+}
+
+impl BottomK {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 1 + 4 + 8 + 4;
+    /// Smallest `k` with a cardinality estimate, which needs the two smallest
+    /// hashes of sets larger than the sketch
+    pub const MIN_K: usize = 2;
+
+    pub fn from_set(shingle_set: &ShingleSet, k: usize, seed: u64) -> Self {
+        let mut hashes: Vec<u64> = shingle_set
+            .shingles
+            .iter()
+            .map(|item| {
+                let mut hasher = FxHasher::default();
+
+                seed.hash(&mut hasher);
+                item.hash(&mut hasher);
+
+                mix(hasher.finish())
+            })
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.truncate(k);
+        Self { k, seed, hashes }
+    }
+
+    /// Whether the sketch holds the hash of every element of its set.
+    pub fn is_exact(&self) -> bool {
+        self.hashes.len() < self.k
+    }
+
+    /// Sketch of the union of both sets, keeping the smaller `k` of the two.
+    pub fn union(&self, b: &Self) -> Result<Self, String> {
+        if self.seed != b.seed {
+            return Err("Bottom-k sketches must share the same seed".to_string());
+        }
+        let k = self.k.min(b.k);
+        let mut hashes = Vec::with_capacity(k);
+        let (mut left, mut right) = (self.hashes.iter().peekable(), b.hashes.iter().peekable());
+        while hashes.len() < k {
+            let next = match (left.peek(), right.peek()) {
+                (Some(&&l), Some(&&r)) if l < r => left.next(),
+                (Some(&&l), Some(&&r)) if r < l => right.next(),
+                (Some(_), Some(_)) => {
+                    right.next();
+                    left.next()
+                }
+                (Some(_), None) => left.next(),
+                (None, Some(_)) => right.next(),
+                (None, None) => None,
+            };
+            match next {
+                Some(&hash) => hashes.push(hash),
+                None => break,
+            }
+        }
+        Ok(Self {
+            k,
+            seed: self.seed,
+            hashes,
+        })
+    }
+
+    /// Estimated number of distinct elements, `(k - 1) / U(k)` where `U(k)` is
+    /// the k-th smallest hash scaled to the unit interval.
+    pub fn cardinality(&self) -> f64 {
+        if self.is_exact() {
+            return self.hashes.len() as f64;
+        }
+        let kth = self.hashes[self.k - 1];
+        (self.k - 1) as f64 / Self::unit(kth)
+    }
+
+    /// Estimated Jaccard similarity: the fraction of the union sketch's hashes
+    /// present in both sketches.
+    pub fn jaccard_similarity(&self, b: &Self) -> Result<f64, String> {
+        let union = self.union(b)?;
+        if union.hashes.is_empty() {
+            return Ok(0.0);
+        }
+        let shared = union
+            .hashes
+            .iter()
+            .filter(|hash| {
+                self.hashes.binary_search(hash).is_ok() && b.hashes.binary_search(hash).is_ok()
+            })
+            .count();
+        Ok(shared as f64 / union.hashes.len() as f64)
+    }
+
+    /// Estimated containment of this sketch's set in `b`'s, `|A ∩ B| / |A|`,
+    /// from this sketch's hashes that are small enough to be decided by `b`.
+    pub fn containment(&self, b: &Self) -> Result<f64, String> {
+        if self.seed != b.seed {
+            return Err("Bottom-k sketches must share the same seed".to_string());
+        }
+        let threshold = match (self.is_exact(), b.is_exact()) {
+            (true, true) => u64::MAX,
+            (true, false) => b.max_hash(),
+            (false, true) => self.max_hash(),
+            (false, false) => self.max_hash().min(b.max_hash()),
+        };
+        let decidable: Vec<&u64> = self
+            .hashes
+            .iter()
+            .filter(|&&hash| hash <= threshold)
+            .collect();
+        if decidable.is_empty() {
+            return Ok(0.0);
+        }
+        let shared = decidable
+            .iter()
+            .filter(|hash| b.hashes.binary_search(hash).is_ok())
+            .count();
+        Ok(shared as f64 / decidable.len() as f64)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + 8 * self.hashes.len());
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&(self.k as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.hashes.len() as u32).to_le_bytes());
+        for hash in &self.hashes {
+            bytes.extend_from_slice(&hash.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let invalid = || "Invalid bottom-k sketch".to_string();
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(invalid());
+        }
+        let k = u32::from_le_bytes(bytes[1..5].try_into().unwrap()) as usize;
+        let seed = u64::from_le_bytes(bytes[5..13].try_into().unwrap());
+        let len = u32::from_le_bytes(bytes[13..17].try_into().unwrap()) as usize;
+        if k < Self::MIN_K || len > k || bytes.len() != Self::HEADER_LEN + 8 * len {
+            return Err(invalid());
+        }
+        let hashes: Vec<u64> = bytes[Self::HEADER_LEN..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        if hashes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(invalid());
+        }
+        Ok(Self { k, seed, hashes })
+    }
+
+    #[inline]
+    fn max_hash(&self) -> u64 {
+        self.hashes.last().copied().unwrap_or(0)
+    }
+
+    #[inline]
+    fn unit(hash: u64) -> f64 {
+        (hash as f64 + 1.0) / 2f64.powi(64)
+    }
+}
@@ -0,0 +1,170 @@
+use crate::minhash::shingleset::ShingleSet;
+use crate::mix;
+
+/// HyperMinHash (Yu & Weber, 2022): a HyperLogLog whose registers also keep
+/// the leading mantissa bits of the minimum hash in each bucket, so the same
+/// sketch estimates cardinalities and Jaccard similarity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperMinHash {
+    registers: Vec<u16>,
+}
+
+impl Default for HyperMinHash {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; Self::NUM_REGISTERS],
+        }
+    }
+}
+
+impl HyperMinHash {
+    /// Bits of the hash selecting the register
+    const P: u32 = 14;
+    /// Bits of the register storing the minimum's mantissa
+    const R: u32 = 10;
+    const NUM_REGISTERS: usize = 1 << Self::P;
+    const MAX_LEADING_ZEROS: u32 = 64 - Self::P + 1;
+
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 2;
+
+    pub fn insert_set(&mut self, shingle_set: &ShingleSet) {
+        for &item in &shingle_set.shingles {
+            self.insert_hash(mix(item as u64));
+        }
+    }
+
+    pub fn insert_hash(&mut self, hash: u64) {
+        let register_idx = (hash >> (64 - Self::P)) as usize;
+        let rest = hash << Self::P;
+        let leading_zeros = (rest.leading_zeros() + 1).min(Self::MAX_LEADING_ZEROS);
+        let mantissa = ((rest << leading_zeros) >> (64 - Self::R)) as u16;
+        let value = ((leading_zeros as u16) << Self::R) | mantissa;
+
+        let current = self.registers[register_idx];
+        if Self::is_smaller_hash(value, current) {
+            self.registers[register_idx] = value;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (register, &value) in self.registers.iter_mut().zip(&other.registers) {
+            if Self::is_smaller_hash(value, *register) {
+                *register = value;
+            }
+        }
+    }
+
+    pub fn cardinality(&self) -> f64 {
+        let m = Self::NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let mut empty = 0;
+        let mut sum = 0.0;
+        for &register in &self.registers {
+            let leading_zeros = Self::leading_zeros(register);
+            if leading_zeros == 0 {
+                empty += 1;
+            }
+            sum += 0.5_f64.powi(leading_zeros as i32);
+        }
+
+        let estimate = alpha * m * m / sum;
+        if estimate <= 2.5 * m && empty > 0 {
+            m * (m / empty as f64).ln() // Linear counting for small cardinalities
+        } else {
+            estimate
+        }
+    }
+
+    pub fn jaccard_similarity(&self, b: &Self) -> f64 {
+        let mut matches = 0;
+        let mut non_empty = 0;
+        for (&left, &right) in self.registers.iter().zip(&b.registers) {
+            if left != 0 || right != 0 {
+                non_empty += 1;
+                if left == right {
+                    matches += 1;
+                }
+            }
+        }
+        if non_empty == 0 {
+            return 0.0;
+        }
+
+        // Only registers outside the intersection can match by chance, so chance
+        // matches scale with `1 - J`
+        let collisions = Self::expected_collisions(self.cardinality(), b.cardinality())
+            .min(non_empty as f64 - 1.0);
+        ((matches as f64 - collisions) / (non_empty as f64 - collisions)).clamp(0.0, 1.0)
+    }
+
+    pub fn intersection(&self, b: &Self) -> f64 {
+        let mut union = self.clone();
+        union.merge(b);
+        self.jaccard_similarity(b) * union.cardinality()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + 2 * Self::NUM_REGISTERS);
+        bytes.push(Self::VERSION);
+        bytes.push(Self::P as u8);
+        for register in &self.registers {
+            bytes.extend_from_slice(&register.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::HEADER_LEN + 2 * Self::NUM_REGISTERS
+            || bytes[0] != Self::VERSION
+            || bytes[1] != Self::P as u8
+        {
+            return Err("Invalid HyperMinHash sketch".to_string());
+        }
+        let registers = bytes[Self::HEADER_LEN..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        Ok(Self { registers })
+    }
+
+    #[inline]
+    fn leading_zeros(register: u16) -> u32 {
+        (register >> Self::R) as u32
+    }
+
+    /// Registers hold the minimum hash of their bucket: more leading zeros, then
+    /// a smaller mantissa, means a smaller hash.
+    #[inline]
+    fn is_smaller_hash(value: u16, current: u16) -> bool {
+        let (value_lz, current_lz) = (Self::leading_zeros(value), Self::leading_zeros(current));
+        value_lz > current_lz || (value_lz == current_lz && value_lz > 0 && value < current)
+    }
+
+    /// Expected number of registers on which sketches of two disjoint sets with
+    /// `n` and `m` elements agree by chance.
+    fn expected_collisions(n: f64, m: f64) -> f64 {
+        let num_registers = Self::NUM_REGISTERS as f64;
+        let (n, m) = (n / num_registers, m / num_registers);
+        let mantissas = (1u32 << Self::R) as f64;
+
+        // P(register minimum falls in [lower, upper)) when the register receives a
+        // Poisson-distributed number of uniform hashes with mean `k`
+        let prob = |k: f64, lower: f64, upper: f64| (-k * lower).exp() - (-k * upper).exp();
+
+        let mut collisions = 0.0;
+        for leading_zeros in 1..Self::MAX_LEADING_ZEROS {
+            let scale = 0.5_f64.powi(leading_zeros as i32);
+            for mantissa in 0..(1u32 << Self::R) {
+                let lower = scale * (1.0 + mantissa as f64 / mantissas);
+                let upper = scale * (1.0 + (mantissa + 1) as f64 / mantissas);
+                let p_n = prob(n, lower, upper);
+                if p_n == 0.0 {
+                    continue;
+                }
+                collisions += p_n * prob(m, lower, upper);
+            }
+        }
+        collisions * num_registers
+    }
+}